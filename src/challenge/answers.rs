@@ -0,0 +1,167 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+const ANSWERS_FILE: &str = "answers.toml";
+
+/// Known-correct answers, keyed by day, then input name, then part
+///
+/// ```toml
+/// [day1.day1_ex]
+/// part1 = "7"
+/// part2 = "5"
+/// ```
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Ledger(BTreeMap<String, BTreeMap<String, BTreeMap<String, String>>>);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict {
+    Match,
+    Differs(String),
+    New,
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Verdict::Match => write!(f, "correct"),
+            Verdict::Differs(expected) => write!(f, "INCORRECT, expected {}", expected),
+            Verdict::New => write!(f, "new"),
+        }
+    }
+}
+
+fn day_key(day: u32) -> String {
+    format!("day{}", day)
+}
+
+fn part_key(part: u32) -> String {
+    format!("part{}", part)
+}
+
+pub fn ledger_path() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join(ANSWERS_FILE)
+}
+
+impl Ledger {
+    pub fn parse(input: &str) -> Result<Ledger> {
+        toml::from_str(input).context("could not parse answer ledger")
+    }
+
+    /// Load the ledger, treating a missing file as an empty ledger
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Ledger> {
+        let path = path.as_ref();
+        if !path.exists() {
+            log::debug!("no answer ledger at {}", path.display());
+            return Ok(Ledger::default());
+        }
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Unable to open path: {}", path.display()))?;
+        Ledger::parse(&content)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let content = toml::to_string(self).context("could not serialize answer ledger")?;
+        fs::write(path, content)
+            .with_context(|| format!("Unable to write path: {}", path.display()))
+    }
+
+    pub fn get(&self, day: u32, input: &str, part: u32) -> Option<&str> {
+        self.0
+            .get(&day_key(day))
+            .and_then(|inputs| inputs.get(input))
+            .and_then(|parts| parts.get(&part_key(part)))
+            .map(|s| s.as_str())
+    }
+
+    pub fn check(&self, day: u32, input: &str, part: u32, answer: &str) -> Verdict {
+        match self.get(day, input, part) {
+            Some(expected) if expected == answer => Verdict::Match,
+            Some(expected) => Verdict::Differs(expected.to_string()),
+            None => Verdict::New,
+        }
+    }
+
+    pub fn record(&mut self, day: u32, input: &str, part: u32, answer: &str) {
+        self.0
+            .entry(day_key(day))
+            .or_default()
+            .entry(input.to_string())
+            .or_default()
+            .insert(part_key(part), answer.to_string());
+    }
+
+    /// Every recorded answer as `(day, input name, part, answer)`
+    #[cfg(test)]
+    pub fn entries(&self) -> impl Iterator<Item = (u32, &str, u32, &str)> {
+        self.0.iter().flat_map(|(day, inputs)| {
+            let day = day.trim_start_matches("day").parse::<u32>().unwrap_or(0);
+            inputs.iter().flat_map(move |(input, parts)| {
+                parts.iter().map(move |(part, answer)| {
+                    let part = part.trim_start_matches("part").parse::<u32>().unwrap_or(0);
+                    (day, input.as_str(), part, answer.as_str())
+                })
+            })
+        })
+    }
+}
+
+/// The ledger's answer for one part of a day's input, for the per-day tests
+#[cfg(test)]
+pub fn expected(day: u32, input: &str, part: u32) -> String {
+    Ledger::parse(include_str!("../../answers.toml"))
+        .unwrap()
+        .get(day, input, part)
+        .unwrap_or_else(|| panic!("no answer for day {} {} part {}", day, input, part))
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::challenge::lookup;
+    const LEDGER: &str = include_str!("../../answers.toml");
+
+    #[test]
+    fn verify_ledger() {
+        let ledger = Ledger::parse(LEDGER).unwrap();
+        for (day, name, part, expected) in ledger.entries() {
+            let solution = lookup(day).unwrap();
+            let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("input")
+                .join(name);
+            let data = fs::read_to_string(path).unwrap();
+            assert_eq!(
+                solution.solve(part, &data).unwrap(),
+                expected,
+                "day {} {} part {}",
+                day,
+                name,
+                part
+            )
+        }
+    }
+    #[test]
+    fn check_verdicts() {
+        let mut ledger = Ledger::default();
+        assert_eq!(ledger.check(1, "day1", 1, "7"), Verdict::New);
+        ledger.record(1, "day1", 1, "7");
+        assert_eq!(ledger.check(1, "day1", 1, "7"), Verdict::Match);
+        assert_eq!(
+            ledger.check(1, "day1", 1, "8"),
+            Verdict::Differs("7".to_string())
+        );
+    }
+    #[test]
+    fn roundtrip() {
+        let ledger = Ledger::parse(LEDGER).unwrap();
+        let reparsed = Ledger::parse(&toml::to_string(&ledger).unwrap()).unwrap();
+        assert_eq!(ledger.entries().count(), reparsed.entries().count());
+    }
+}
@@ -0,0 +1,216 @@
+use super::{input, lookup, parse_arg, Solution, SOLUTIONS};
+use anyhow::{anyhow as ah, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    time::{Duration, Instant},
+};
+
+const DEFAULT_ITERATIONS: usize = 100;
+const DEFAULT_WARMUP: usize = 5;
+const DEFAULT_THRESHOLD_PERCENT: f64 = 10.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Stats {
+    pub min_ns: u64,
+    pub median_ns: u64,
+    pub mean_ns: u64,
+    pub stddev_ns: u64,
+}
+
+impl Stats {
+    pub fn from_samples(samples: &[Duration]) -> Stats {
+        assert!(!samples.is_empty(), "no samples to summarize");
+        let mut ns = samples
+            .iter()
+            .map(|d| d.as_nanos() as f64)
+            .collect::<Vec<_>>();
+        ns.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let len = ns.len();
+        let median = if len % 2 == 0 {
+            (ns[len / 2 - 1] + ns[len / 2]) / 2.0
+        } else {
+            ns[len / 2]
+        };
+        let mean = ns.iter().sum::<f64>() / len as f64;
+        let variance = ns.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / len as f64;
+        Stats {
+            min_ns: ns[0] as u64,
+            median_ns: median as u64,
+            mean_ns: mean as u64,
+            stddev_ns: variance.sqrt() as u64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchResult {
+    pub day: u32,
+    pub part: u32,
+    pub parse: Stats,
+    pub solve: Stats,
+}
+
+impl BenchResult {
+    fn key(&self) -> String {
+        format!("day{}/part{}", self.day, self.part)
+    }
+    /// Median time to parse and then solve
+    pub fn total_ns(&self) -> u64 {
+        self.parse.median_ns + self.solve.median_ns
+    }
+}
+
+/// Saved results, keyed by `dayN/partM`
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Baseline(BTreeMap<String, BenchResult>);
+
+impl Baseline {
+    fn load(path: &str) -> Result<Baseline> {
+        let content =
+            fs::read_to_string(path).with_context(|| format!("Unable to open path: {}", path))?;
+        serde_json::from_str(&content).context("could not parse bench baseline")
+    }
+    fn save(&self, path: &str) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(path, content).with_context(|| format!("Unable to write path: {}", path))
+    }
+}
+
+fn time<F: FnMut() -> Result<()>>(iterations: usize, warmup: usize, mut f: F) -> Result<Stats> {
+    for _ in 0..warmup {
+        f()?;
+    }
+    let samples = (0..iterations)
+        .map(|_| {
+            let start = Instant::now();
+            f().map(|_| start.elapsed())
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Stats::from_samples(&samples))
+}
+
+pub fn bench_part(
+    solution: &Solution,
+    part: u32,
+    data: &str,
+    iterations: usize,
+    warmup: usize,
+) -> Result<BenchResult> {
+    let solve = solution
+        .part(part)
+        .ok_or_else(|| ah!("unimplemented challenge day {} part {}", solution.day, part))?;
+    log::debug!("benchmarking day {}:{}", solution.day, part);
+    let parse = time(iterations, warmup, || (solution.parse)(data).map(drop))?;
+    let parsed = (solution.parse)(data)?;
+    let solve = time(iterations, warmup, || solve(parsed.as_ref()).map(drop))?;
+    Ok(BenchResult {
+        day: solution.day,
+        part,
+        parse,
+        solve,
+    })
+}
+
+fn fmt_ns(ns: u64) -> String {
+    format!("{:.3?}", Duration::from_nanos(ns))
+}
+
+fn print_results(results: &[BenchResult], baseline: Option<&Baseline>, threshold: f64) -> usize {
+    println!(
+        "{:>3} {:>4} {:>12} {:>12} {:>12} {:>12} {:>12} {:>12}  baseline",
+        "day", "part", "parse", "solve min", "solve median", "solve mean", "solve stddev", "total"
+    );
+    let mut regressions = 0;
+    for r in results {
+        let comparison = baseline.and_then(|b| b.0.get(&r.key())).map(|b| {
+            let change = r.total_ns() as f64 / b.total_ns().max(1) as f64 - 1.0;
+            if change * 100.0 > threshold {
+                regressions += 1;
+                format!("{:+.1}% REGRESSION", change * 100.0)
+            } else {
+                format!("{:+.1}%", change * 100.0)
+            }
+        });
+        println!(
+            "{:>3} {:>4} {:>12} {:>12} {:>12} {:>12} {:>12} {:>12}  {}",
+            r.day,
+            r.part,
+            fmt_ns(r.parse.median_ns),
+            fmt_ns(r.solve.min_ns),
+            fmt_ns(r.solve.median_ns),
+            fmt_ns(r.solve.mean_ns),
+            fmt_ns(r.solve.stddev_ns),
+            fmt_ns(r.total_ns()),
+            comparison.as_deref().unwrap_or("-"),
+        );
+    }
+    regressions
+}
+
+pub fn run(args: &clap::ArgMatches) -> Result<()> {
+    let iterations = parse_arg(args, "iterations", DEFAULT_ITERATIONS)?;
+    let warmup = parse_arg(args, "warmup", DEFAULT_WARMUP)?;
+    let threshold = parse_arg(args, "threshold", DEFAULT_THRESHOLD_PERCENT)?;
+    if iterations == 0 {
+        anyhow::bail!("need at least one iteration");
+    }
+    let example = args.is_present("example");
+
+    let solutions = match args.value_of("day") {
+        Some(d) => {
+            let day = d.parse::<u32>()?;
+            vec![lookup(day).ok_or_else(|| ah!("unimplemented challenge day {}", day))?]
+        }
+        None => SOLUTIONS.iter().collect(),
+    };
+    let parts = match args.value_of("part") {
+        Some(p) => vec![p.parse::<u32>()?],
+        None => vec![1, 2],
+    };
+
+    let mut results = Vec::new();
+    for solution in solutions {
+        let data = input::load(None, solution, example)?.data;
+        for part in &parts {
+            results.push(bench_part(solution, *part, &data, iterations, warmup)?);
+        }
+    }
+
+    let baseline = args.value_of("baseline").map(Baseline::load).transpose()?;
+    let regressions = print_results(&results, baseline.as_ref(), threshold);
+
+    if let Some(path) = args.value_of("save") {
+        let mut saved = Baseline::default();
+        for r in results {
+            saved.0.insert(r.key(), r);
+        }
+        saved.save(path)?;
+        log::info!("saved baseline to {}", path);
+    }
+
+    if regressions > 0 {
+        anyhow::bail!("{} parts regressed more than {}%", regressions, threshold);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarize_samples() {
+        let samples = [4, 1, 3, 2]
+            .iter()
+            .map(|ms| Duration::from_millis(*ms))
+            .collect::<Vec<_>>();
+        let stats = Stats::from_samples(&samples);
+        assert_eq!(stats.min_ns, 1_000_000);
+        assert_eq!(stats.median_ns, 2_500_000);
+        assert_eq!(stats.mean_ns, 2_500_000);
+        assert_eq!(stats.stddev_ns, 1_118_033);
+    }
+}
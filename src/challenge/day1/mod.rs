@@ -1,25 +1,64 @@
+use super::{
+    input,
+    solution::{Day, Solution, Streaming},
+};
 use anyhow::{Context, Result};
+use std::{collections::VecDeque, io::BufRead};
 
-pub fn part1(input: &str) -> Result<String> {
-    let sonar = parse(input)?;
-    let increases = count_increases(sonar.iter().cloned());
-    Ok(format!("{:?}", increases))
+pub struct Day1;
+
+pub const SOLUTION: Solution = Solution::streaming::<Day1>();
+
+impl Day for Day1 {
+    const DAY: u32 = 1;
+    const TITLE: &'static str = "Sonar Sweep";
+    const EXAMPLE: &'static str = include_str!("../../../input/day1_ex");
+
+    type Input = Vec<i64>;
+    type Answer1 = u64;
+    type Answer2 = u64;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        parse(input)
+    }
+    fn part1(sonar: &Self::Input) -> Result<u64> {
+        part1(sonar)
+    }
+    fn part2(sonar: &Self::Input) -> Result<u64> {
+        part2(sonar)
+    }
+}
+
+impl Streaming for Day1 {
+    fn stream_part1(reader: &mut dyn BufRead) -> Result<u64> {
+        input::process_results(depths(reader), |depths| count_increases(depths))
+    }
+    fn stream_part2(reader: &mut dyn BufRead) -> Result<u64> {
+        input::process_results(depths(reader), |depths| {
+            count_increases(window_sums(depths, 3))
+        })
+    }
 }
 
-pub fn part2(input: &str) -> Result<String> {
-    let sonar = parse(input)?;
-    let increases = count_increases(window_sums(&sonar, 3));
-    Ok(format!("{:?}", increases))
+pub fn part1(sonar: &[i64]) -> Result<u64> {
+    Ok(count_increases(sonar.iter().cloned()))
+}
+
+pub fn part2(sonar: &[i64]) -> Result<u64> {
+    Ok(count_increases(window_sums(sonar.iter().cloned(), 3)))
+}
+
+fn parse_depth(l: &str) -> Result<i64> {
+    l.parse::<i64>()
+        .with_context(|| format!("could not parse number: {:?}", l))
 }
 
 fn parse(input: &str) -> Result<Vec<i64>> {
-    input
-        .lines()
-        .map(|l| {
-            l.parse::<i64>()
-                .with_context(|| format!("could not parse number: {:?}", l))
-        })
-        .collect()
+    input.lines().map(parse_depth).collect()
+}
+
+fn depths(reader: &mut dyn BufRead) -> impl Iterator<Item = Result<i64>> + '_ {
+    input::lines(reader).map(|l| l.and_then(|l| parse_depth(&l)))
 }
 
 fn count_increases(mut depths: impl Iterator<Item = i64>) -> u64 {
@@ -38,35 +77,73 @@ fn count_increases(mut depths: impl Iterator<Item = i64>) -> u64 {
     count
 }
 
-fn window_sums(data: &[i64], window: usize) -> impl Iterator<Item = i64> + '_ {
-    data.windows(window).map(|x| x.iter().sum::<i64>())
+/// Sums of each `window` consecutive depths, keeping only the current window in memory
+fn window_sums(data: impl Iterator<Item = i64>, window: usize) -> impl Iterator<Item = i64> {
+    let mut current = VecDeque::with_capacity(window + 1);
+    data.filter_map(move |d| {
+        current.push_back(d);
+        if current.len() > window {
+            current.pop_front();
+        }
+        if current.len() == window {
+            Some(current.iter().sum::<i64>())
+        } else {
+            None
+        }
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::challenge::answers::expected;
     const INPUT: &str = include_str!("../../../input/day1");
     const EX: &str = include_str!("../../../input/day1_ex");
 
     #[test]
     fn verify_p1() {
-        assert_eq!(part1(INPUT).unwrap().as_str(), "1451")
+        assert_eq!(
+            part1(&parse(INPUT).unwrap()).unwrap().to_string(),
+            expected(1, "day1", 1)
+        )
     }
 
     #[test]
     fn verify_p2() {
-        assert_eq!(part2(INPUT).unwrap().as_str(), "1395")
+        assert_eq!(
+            part2(&parse(INPUT).unwrap()).unwrap().to_string(),
+            expected(1, "day1", 2)
+        )
     }
 
     #[test]
     fn verify_example() {
-        assert_eq!(part1(EX).unwrap().as_str(), "7")
+        assert_eq!(
+            part1(&parse(EX).unwrap()).unwrap().to_string(),
+            expected(1, "day1_ex", 1)
+        )
     }
 
     #[test]
     fn verify_example_windows() {
         let ex_data = parse(EX).unwrap();
-        let windowed = window_sums(&ex_data, 3).collect::<Vec<_>>();
+        let windowed = window_sums(ex_data.iter().cloned(), 3).collect::<Vec<_>>();
         assert_eq!(windowed, vec![607, 618, 618, 617, 647, 716, 769, 792,])
     }
+
+    #[test]
+    fn verify_streaming() {
+        assert_eq!(
+            Day1::stream_part1(&mut INPUT.as_bytes())
+                .unwrap()
+                .to_string(),
+            expected(1, "day1", 1)
+        );
+        assert_eq!(
+            Day1::stream_part2(&mut INPUT.as_bytes())
+                .unwrap()
+                .to_string(),
+            expected(1, "day1", 2)
+        );
+    }
 }
@@ -1,30 +1,77 @@
+use super::{
+    input,
+    solution::{Day, Solution, Streaming},
+};
 use anyhow::{Context, Result};
-use std::str::FromStr;
+use std::{io::BufRead, str::FromStr};
 
-pub fn part1(input: &str) -> Result<String> {
-    let commands = parse(input)?;
+pub struct Day2;
+
+pub const SOLUTION: Solution = Solution::streaming::<Day2>();
+
+impl Day for Day2 {
+    const DAY: u32 = 2;
+    const TITLE: &'static str = "Dive!";
+    const EXAMPLE: &'static str = include_str!("../../../input/day2_ex");
+
+    type Input = Vec<Command>;
+    type Answer1 = i64;
+    type Answer2 = i64;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        parse(input)
+    }
+    fn part1(commands: &Self::Input) -> Result<i64> {
+        part1(commands)
+    }
+    fn part2(commands: &Self::Input) -> Result<i64> {
+        part2(commands)
+    }
+}
+
+impl Streaming for Day2 {
+    fn stream_part1(reader: &mut dyn BufRead) -> Result<i64> {
+        input::process_results(commands(reader), |commands| follow(commands))?
+    }
+    fn stream_part2(reader: &mut dyn BufRead) -> Result<i64> {
+        input::process_results(commands(reader), |commands| follow_with_aim(commands))?
+    }
+}
+
+pub fn part1(commands: &[Command]) -> Result<i64> {
+    follow(commands.iter().copied())
+}
+
+pub fn part2(commands: &[Command]) -> Result<i64> {
+    follow_with_aim(commands.iter().copied())
+}
+
+fn follow(commands: impl Iterator<Item = Command>) -> Result<i64> {
     let mut loc = Location::default();
     for c in commands {
-        loc.update(&c)
+        loc.update(&c)?
     }
     log::trace!("final location: {:?}", loc);
-    Ok(format!("{}", loc.x * loc.depth))
+    loc.product()
 }
 
-pub fn part2(input: &str) -> Result<String> {
-    let commands = parse(input)?;
+fn follow_with_aim(commands: impl Iterator<Item = Command>) -> Result<i64> {
     let mut loc = Location::default();
     for c in commands {
-        loc.update_with_aim(&c)
+        loc.update_with_aim(&c)?
     }
     log::trace!("final location: {:?}", loc);
-    Ok(format!("{}", loc.x * loc.depth))
+    loc.product()
 }
 
 fn parse(input: &str) -> Result<Vec<Command>> {
     input.lines().map(|l| l.parse::<Command>()).collect()
 }
 
+fn commands(reader: &mut dyn BufRead) -> impl Iterator<Item = Result<Command>> + '_ {
+    input::lines(reader).map(|l| l.and_then(|l| l.parse::<Command>()))
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum Direction {
     Up,
@@ -46,7 +93,7 @@ impl FromStr for Direction {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
-struct Command {
+pub struct Command {
     direction: Direction,
     units: u32,
 }
@@ -70,53 +117,126 @@ impl FromStr for Command {
 
 #[derive(Debug, Clone, Copy, PartialEq, Default)]
 struct Location {
-    x: i32,
-    aim: i32,
-    depth: i32,
+    x: i64,
+    aim: i64,
+    depth: i64,
+}
+
+fn overflow(loc: &Location, cmd: &Command) -> anyhow::Error {
+    anyhow::anyhow!("location overflows following {:?} from {:?}", cmd, loc)
 }
 
 impl Location {
-    fn update(&mut self, cmd: &Command) {
-        let delta = cmd.units as i32;
-        match cmd.direction {
-            Direction::Up => self.depth -= delta,
-            Direction::Down => self.depth += delta,
-            Direction::Forward => self.x += delta,
-        }
-    }
-    fn update_with_aim(&mut self, cmd: &Command) {
-        let delta = cmd.units as i32;
-        match cmd.direction {
-            Direction::Up => self.aim -= delta,
-            Direction::Down => self.aim += delta,
-            Direction::Forward => {
-                self.x += delta;
-                self.depth += delta * self.aim;
-            }
-        }
+    fn update(&mut self, cmd: &Command) -> Result<()> {
+        let delta = i64::from(cmd.units);
+        let next = match cmd.direction {
+            Direction::Up => self.depth.checked_sub(delta).map(|depth| (self.x, depth)),
+            Direction::Down => self.depth.checked_add(delta).map(|depth| (self.x, depth)),
+            Direction::Forward => self.x.checked_add(delta).map(|x| (x, self.depth)),
+        };
+        let (x, depth) = next.ok_or_else(|| overflow(self, cmd))?;
+        self.x = x;
+        self.depth = depth;
+        Ok(())
+    }
+    fn update_with_aim(&mut self, cmd: &Command) -> Result<()> {
+        let delta = i64::from(cmd.units);
+        let next = match cmd.direction {
+            Direction::Up => self
+                .aim
+                .checked_sub(delta)
+                .map(|aim| (self.x, aim, self.depth)),
+            Direction::Down => self
+                .aim
+                .checked_add(delta)
+                .map(|aim| (self.x, aim, self.depth)),
+            Direction::Forward => self.x.checked_add(delta).and_then(|x| {
+                delta
+                    .checked_mul(self.aim)
+                    .and_then(|d| self.depth.checked_add(d))
+                    .map(|depth| (x, self.aim, depth))
+            }),
+        };
+        let (x, aim, depth) = next.ok_or_else(|| overflow(self, cmd))?;
+        self.x = x;
+        self.aim = aim;
+        self.depth = depth;
+        Ok(())
+    }
+    fn product(&self) -> Result<i64> {
+        self.x
+            .checked_mul(self.depth)
+            .ok_or_else(|| anyhow::anyhow!("answer overflows at {:?}", self))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::challenge::answers::expected;
     const INPUT: &str = include_str!("../../../input/day2");
     const EX: &str = include_str!("../../../input/day2_ex");
 
     #[test]
     fn verify_p1() {
-        assert_eq!(part1(INPUT).unwrap().as_str(), "1648020")
+        assert_eq!(
+            part1(&parse(INPUT).unwrap()).unwrap().to_string(),
+            expected(2, "day2", 1)
+        )
     }
     #[test]
     fn verify_p2() {
-        assert_eq!(part2(INPUT).unwrap().as_str(), "1759818555")
+        assert_eq!(
+            part2(&parse(INPUT).unwrap()).unwrap().to_string(),
+            expected(2, "day2", 2)
+        )
     }
     #[test]
     fn check_example() {
-        assert_eq!(part1(EX).unwrap().as_str(), "150")
+        assert_eq!(
+            part1(&parse(EX).unwrap()).unwrap().to_string(),
+            expected(2, "day2_ex", 1)
+        )
     }
     #[test]
     fn check_example_pt2() {
-        assert_eq!(part2(EX).unwrap().as_str(), "900")
+        assert_eq!(
+            part2(&parse(EX).unwrap()).unwrap().to_string(),
+            expected(2, "day2_ex", 2)
+        )
+    }
+    #[test]
+    fn verify_streaming() {
+        assert_eq!(
+            Day2::stream_part1(&mut INPUT.as_bytes())
+                .unwrap()
+                .to_string(),
+            expected(2, "day2", 1)
+        );
+        assert_eq!(
+            Day2::stream_part2(&mut INPUT.as_bytes())
+                .unwrap()
+                .to_string(),
+            expected(2, "day2", 2)
+        );
+    }
+    #[test]
+    fn stream_repeated_input() {
+        let repeated = INPUT.repeat(4);
+        let commands = parse(&repeated).unwrap();
+        let p1 = Day2::stream_part1(&mut repeated.as_bytes()).unwrap();
+        assert_eq!(p1, 16 * part1(&parse(INPUT).unwrap()).unwrap());
+        assert_eq!(p1, part1(&commands).unwrap());
+        assert_eq!(
+            Day2::stream_part2(&mut repeated.as_bytes()).unwrap(),
+            part2(&commands).unwrap()
+        );
+    }
+    #[test]
+    fn location_overflow() {
+        let far = "forward 4294967295\ndown 4294967295\n";
+        assert!(Day2::stream_part1(&mut far.as_bytes()).is_err());
+        let steep = "down 4294967295\n".repeat(3) + &"forward 4294967295\n".repeat(1000);
+        assert!(Day2::stream_part2(&mut steep.as_bytes()).is_err());
     }
 }
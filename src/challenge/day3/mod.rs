@@ -1,22 +1,45 @@
+use super::solution::{Day, Solution};
 use anyhow::{Context, Result};
 use std::fmt;
 
-pub fn part1(input: &str) -> Result<String> {
-    let diag = parse(input)?;
-    let max_bits = get_max_bits_for_diag(&diag);
-    let g = gamma(&diag, max_bits);
-    let e = epsilon(&diag, max_bits);
+pub struct Day3;
+
+pub const SOLUTION: Solution = Solution::new::<Day3>();
+
+impl Day for Day3 {
+    const DAY: u32 = 3;
+    const TITLE: &'static str = "Binary Diagnostic";
+    const EXAMPLE: &'static str = include_str!("../../../input/day3_ex");
+
+    type Input = Vec<u32>;
+    type Answer1 = u32;
+    type Answer2 = u32;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        parse(input)
+    }
+    fn part1(diag: &Self::Input) -> Result<u32> {
+        part1(diag)
+    }
+    fn part2(diag: &Self::Input) -> Result<u32> {
+        part2(diag)
+    }
+}
+
+pub fn part1(diag: &[u32]) -> Result<u32> {
+    let max_bits = get_max_bits_for_diag(diag);
+    let g = gamma(diag, max_bits);
+    let e = epsilon(diag, max_bits);
     log::debug!("gamma: {:0b} epsilon: {:0b}", g, e,);
-    Ok(format!("{:?}", g * e))
+    Ok(g * e)
 }
 
-pub fn part2(input: &str) -> Result<String> {
-    let diag = parse(input)?;
-    let max_bits = get_max_bits_for_diag(&diag);
-    let o2 = oxygen(&diag, max_bits);
-    let co2 = carbon_dioxide(&diag, max_bits);
+pub fn part2(diag: &[u32]) -> Result<u32> {
+    let max_bits = get_max_bits_for_diag(diag);
+    let o2 = oxygen(diag, max_bits);
+    let co2 = carbon_dioxide(diag, max_bits);
     log::debug!("oxygen: {}, carbon dioxide: {}", o2, co2);
-    Ok(format!("{:?}", o2 * co2))
+    Ok(o2 * co2)
 }
 
 fn parse(input: &str) -> Result<Vec<u32>> {
@@ -144,24 +167,37 @@ impl<'a> fmt::Debug for BinList<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::challenge::answers::expected;
     const INPUT: &str = include_str!("../../../input/day3");
     const EX: &str = include_str!("../../../input/day3_ex");
 
     #[test]
     fn verify_p1() {
-        assert_eq!(part1(INPUT).unwrap().as_str(), "738234")
+        assert_eq!(
+            part1(&parse(INPUT).unwrap()).unwrap().to_string(),
+            expected(3, "day3", 1)
+        )
     }
     #[test]
     fn verify_p2() {
-        assert_eq!(part2(INPUT).unwrap().as_str(), "3969126")
+        assert_eq!(
+            part2(&parse(INPUT).unwrap()).unwrap().to_string(),
+            expected(3, "day3", 2)
+        )
     }
     #[test]
     fn verify_example() {
-        assert_eq!(part1(EX).unwrap().as_str(), "198")
+        assert_eq!(
+            part1(&parse(EX).unwrap()).unwrap().to_string(),
+            expected(3, "day3_ex", 1)
+        )
     }
     #[test]
     fn verify_example_p2() {
-        assert_eq!(part2(EX).unwrap().as_str(), "230")
+        assert_eq!(
+            part2(&parse(EX).unwrap()).unwrap().to_string(),
+            expected(3, "day3_ex", 2)
+        )
     }
 
     #[test]
@@ -1,11 +1,39 @@
+use anyhow::Result;
 use aoc::grid::{fixed_grid::FixedGrid, point::Point};
-use std::fmt;
+use std::{collections::HashMap, fmt};
+
+/// The shape of every card, and which numbers can be on them
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BingoRules {
+    pub width: usize,
+    pub height: usize,
+    /// Numbers run from 0 up to, but not including, this
+    pub numbers: usize,
+}
+
+impl Default for BingoRules {
+    fn default() -> Self {
+        BingoRules {
+            width: 5,
+            height: 5,
+            numbers: 100,
+        }
+    }
+}
 
-const BINGO_DIMM: usize = 5;
-const BINGO_WIDTH: usize = BINGO_DIMM;
-const BINGO_HEIGHT: usize = BINGO_DIMM;
-pub const BINGO_LEN: usize = BINGO_WIDTH * BINGO_HEIGHT;
-const MAX_NUMBER: usize = 100;
+impl BingoRules {
+    /// How many numbers are on each card
+    pub fn card_len(&self) -> usize {
+        self.width * self.height
+    }
+
+    fn check_number(&self, number: u32) -> Result<u32> {
+        if number as usize >= self.numbers {
+            anyhow::bail!("number {} is outside 0..{}", number, self.numbers);
+        }
+        Ok(number)
+    }
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Default)]
 struct Mark(bool);
@@ -23,10 +51,21 @@ impl fmt::Display for Mark {
 #[derive(Debug)]
 struct BingoCard {
     marks: FixedGrid<Mark>,
+    width: usize,
+    height: usize,
     won: bool,
 }
 
 impl BingoCard {
+    fn new(width: usize, height: usize) -> Self {
+        BingoCard {
+            marks: FixedGrid::from_dimm(height, width),
+            width,
+            height,
+            won: false,
+        }
+    }
+
     fn check_winner_at(&self, idx: usize) -> bool {
         if self.won {
             return false;
@@ -51,10 +90,10 @@ impl BingoCard {
     }
 
     fn col(&self, idx: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
-        (0..BINGO_HEIGHT).map(move |y| (idx, y))
+        (0..self.height).map(move |y| (idx, y))
     }
     fn row(&self, idx: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
-        (0..BINGO_WIDTH).map(move |x| (x, idx))
+        (0..self.width).map(move |x| (x, idx))
     }
 
     fn check_range(&self, points: impl Iterator<Item = (usize, usize)>) -> bool {
@@ -64,15 +103,6 @@ impl BingoCard {
     }
 }
 
-impl Default for BingoCard {
-    fn default() -> Self {
-        BingoCard {
-            marks: FixedGrid::from_dimm(BINGO_HEIGHT, BINGO_WIDTH),
-            won: false,
-        }
-    }
-}
-
 #[derive(Debug, Default)]
 struct BingoNumberStats {
     seen: bool,
@@ -80,7 +110,9 @@ struct BingoNumberStats {
 }
 
 pub struct MultiBingo {
-    mapping: Vec<BingoNumberStats>,
+    rules: BingoRules,
+    /// Only the numbers that are on a card, so a wide range costs nothing
+    mapping: HashMap<u32, BingoNumberStats>,
     cards: Vec<BingoCard>,
 }
 
@@ -96,59 +128,74 @@ impl fmt::Debug for MultiBingo {
 
 impl Default for MultiBingo {
     fn default() -> Self {
-        let mut mapping = Vec::with_capacity(MAX_NUMBER);
-        mapping.resize_with(MAX_NUMBER, BingoNumberStats::default);
-
-        MultiBingo {
-            mapping,
-            cards: Vec::new(),
-        }
+        MultiBingo::new(BingoRules::default())
     }
 }
 
 struct BingoEntry {
     seen: bool,
     card_idx: usize,
-    number: u8,
+    number: u32,
 }
 
 impl MultiBingo {
-    pub fn add_card(&mut self, data: &[u8]) {
-        assert_eq!(
-            data.len(),
-            BINGO_WIDTH * BINGO_HEIGHT,
-            "card was the wrong number of numbers"
-        );
+    pub fn new(rules: BingoRules) -> Self {
+        MultiBingo {
+            rules,
+            mapping: HashMap::new(),
+            cards: Vec::new(),
+        }
+    }
+
+    pub fn add_card(&mut self, data: &[u32]) -> Result<()> {
+        if data.len() != self.rules.card_len() {
+            anyhow::bail!(
+                "card has {} numbers, expected {}",
+                data.len(),
+                self.rules.card_len()
+            );
+        }
+        let numbers = data
+            .iter()
+            .map(|x| self.rules.check_number(*x))
+            .collect::<Result<Vec<_>>>()?;
+        for (pos, number) in numbers.iter().enumerate() {
+            if numbers[..pos].contains(number) {
+                anyhow::bail!("{} is on the card twice", number);
+            }
+        }
         let card_idx = self.cards.len();
-        self.cards.push(BingoCard::default());
-        for (card_pos, x) in data.iter().enumerate() {
-            let idx = *x as usize;
-            self.mapping[idx].cards.push((card_idx, card_pos));
+        self.cards
+            .push(BingoCard::new(self.rules.width, self.rules.height));
+        for (card_pos, number) in numbers.into_iter().enumerate() {
+            let stats = self.mapping.entry(number).or_default();
+            stats.cards.push((card_idx, card_pos));
         }
+        Ok(())
     }
 
     fn flat_iter_bingo(&self) -> impl Iterator<Item = BingoEntry> + '_ {
-        self.mapping
-            .iter()
-            .enumerate()
-            .flat_map(|(num_idx, stats)| {
-                stats.cards.iter().map(move |(idx, _)| BingoEntry {
-                    seen: stats.seen,
-                    card_idx: *idx,
-                    number: num_idx as u8,
-                })
+        self.mapping.iter().flat_map(|(number, stats)| {
+            stats.cards.iter().map(move |(idx, _)| BingoEntry {
+                seen: stats.seen,
+                card_idx: *idx,
+                number: *number,
             })
+        })
     }
 
-    pub fn unmarked_at_card(&self, card_idx: usize) -> impl Iterator<Item = u8> + '_ {
+    pub fn unmarked_at_card(&self, card_idx: usize) -> impl Iterator<Item = u32> + '_ {
         self.flat_iter_bingo()
             .filter(move |e| !e.seen && e.card_idx == card_idx)
             .map(|e| e.number)
     }
 
-    pub fn call_number(&mut self, called: u8) -> Option<usize> {
-        let idx = called as usize;
-        let stats = &mut self.mapping[idx];
+    pub fn call_number(&mut self, called: u32) -> Result<Option<usize>> {
+        let number = self.rules.check_number(called)?;
+        let stats = match self.mapping.get_mut(&number) {
+            Some(stats) => stats,
+            None => return Ok(None),
+        };
         stats.seen = true;
         let mut win = None;
         for (card_idx, card_pos) in &stats.cards {
@@ -158,7 +205,7 @@ impl MultiBingo {
                 win = Some(*card_idx);
             }
         }
-        win
+        Ok(win)
     }
     pub fn get_winner_details(&self, card_idx: usize) -> u64 {
         log::trace!("winner: Card #{}\n{}", card_idx, self.cards[card_idx].marks);
@@ -167,7 +214,7 @@ impl MultiBingo {
             .sum::<u64>()
     }
 
-    pub fn iter<I: Iterator<Item = u8>>(self, iter: I) -> BingoWinners<I> {
+    pub fn iter<I: Iterator<Item = u32>>(self, iter: I) -> BingoWinners<I> {
         BingoWinners { iter, bingo: self }
     }
 }
@@ -177,16 +224,20 @@ pub struct BingoWinners<I> {
     bingo: MultiBingo,
 }
 
-impl<I: Iterator<Item = u8>> Iterator for BingoWinners<I> {
-    type Item = u64;
+impl<I: Iterator<Item = u32>> Iterator for BingoWinners<I> {
+    type Item = Result<u64>;
 
     fn next(&mut self) -> Option<Self::Item> {
         for call in self.iter.by_ref() {
-            if let Some(idx) = self.bingo.call_number(call) {
-                let total = self.bingo.get_winner_details(idx);
-                let win = total * call as u64;
-                log::debug!("winner: #{}, {} x {} = {}", idx, total, call, win);
-                return Some(win);
+            match self.bingo.call_number(call) {
+                Ok(Some(idx)) => {
+                    let total = self.bingo.get_winner_details(idx);
+                    let win = total * call as u64;
+                    log::debug!("winner: #{}, {} x {} = {}", idx, total, call, win);
+                    return Some(Ok(win));
+                }
+                Ok(None) => {}
+                Err(e) => return Some(Err(e)),
             }
         }
         None
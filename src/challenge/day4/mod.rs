@@ -1,35 +1,64 @@
+use super::solution::{Day, Solution};
 use anyhow::{Context, Result};
+use std::collections::HashMap;
 
 mod bingo;
 
-pub fn part1(input: &str) -> Result<String> {
-    let w = iterate_bingo_winners(input)?
-        .next()
-        .ok_or_else(|| anyhow::anyhow!("no winner"))?;
+pub struct Day4;
+
+pub const SOLUTION: Solution = Solution::new::<Day4>();
+
+impl Day for Day4 {
+    const DAY: u32 = 4;
+    const TITLE: &'static str = "Giant Squid";
+    const EXAMPLE: &'static str = include_str!("../../../input/day4_ex");
+
+    type Input = BingoGame;
+    type Answer1 = u64;
+    type Answer2 = u64;
 
-    Ok(format!("{}", w))
+    fn parse(input: &str) -> Result<Self::Input> {
+        parse(input)
+    }
+    fn part1(game: &Self::Input) -> Result<u64> {
+        part1(game)
+    }
+    fn part2(game: &Self::Input) -> Result<u64> {
+        part2(game)
+    }
 }
 
-pub fn part2(input: &str) -> Result<String> {
-    let w = iterate_bingo_winners(input)?
-        .last()
-        .ok_or_else(|| anyhow::anyhow!("no winner"))?;
+#[derive(Debug, Clone)]
+pub struct BingoGame {
+    rules: bingo::BingoRules,
+    called: Vec<u32>,
+    /// Each card's numbers, row by row
+    cards: Vec<Vec<u32>>,
+}
 
-    Ok(format!("{}", w))
+pub fn part1(game: &BingoGame) -> Result<u64> {
+    iterate_bingo_winners(game)?
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("no winner"))?
 }
 
-fn iterate_bingo_winners(input: &str) -> Result<impl Iterator<Item = u64>> {
-    let (called, cards) = parse(input)?;
+pub fn part2(game: &BingoGame) -> Result<u64> {
+    iterate_bingo_winners(game)?
+        .try_fold(None, |_, win| win.map(Some))?
+        .ok_or_else(|| anyhow::anyhow!("no winner"))
+}
 
-    let mut mb = bingo::MultiBingo::default();
+fn iterate_bingo_winners(game: &BingoGame) -> Result<impl Iterator<Item = Result<u64>> + '_> {
+    let mut mb = bingo::MultiBingo::new(game.rules);
 
-    for card in cards.chunks_exact(bingo::BINGO_LEN) {
-        mb.add_card(card);
+    for (idx, card) in game.cards.iter().enumerate() {
+        mb.add_card(card)
+            .with_context(|| format!("bad bingo card #{}", idx))?;
     }
-    Ok(mb.iter(called.into_iter()))
+    Ok(mb.iter(game.called.iter().cloned()))
 }
 
-fn parse(input: &str) -> Result<(Vec<u8>, Vec<u8>)> {
+fn parse(input: &str) -> Result<BingoGame> {
     let mut lines = input.lines();
     let first_line = lines
         .next()
@@ -37,43 +66,222 @@ fn parse(input: &str) -> Result<(Vec<u8>, Vec<u8>)> {
 
     let called = first_line
         .split(',')
-        .map(|num| {
-            num.parse::<u8>()
-                .with_context(|| format!("could not parse number: {:?}", num))
-        })
+        .map(parse_number)
         .collect::<Result<Vec<_>>>()?;
 
-    let cards = lines
-        .flat_map(|l| l.split_whitespace())
-        .map(|num| {
-            num.parse::<u8>()
-                .with_context(|| format!("could not parse number: {:?}", num))
+    // line numbers count from 1, and the called numbers are line 1
+    let blocks = blocks(lines.enumerate().map(|(n, l)| (n + 2, l)));
+
+    // the first card sets the shape of every card
+    let first_card = blocks
+        .first()
+        .ok_or_else(|| anyhow::anyhow!("no bingo cards"))?;
+    let width = words(first_card[0].1).count();
+    let height = first_card.len();
+
+    let cards = blocks
+        .iter()
+        .enumerate()
+        .map(|(idx, block)| {
+            parse_card(block, width, height)
+                .with_context(|| format!("bad bingo card #{} at line {}", idx, block[0].0))
         })
         .collect::<Result<Vec<_>>>()?;
 
-    Ok((called, cards))
+    // every number in the game is in range, and `MultiBingo` only stores the
+    // numbers on the cards, so even a huge range is cheap
+    let largest = called
+        .iter()
+        .chain(cards.iter().flatten())
+        .max()
+        .copied()
+        .unwrap_or(0);
+    let rules = bingo::BingoRules {
+        width,
+        height,
+        numbers: largest as usize + 1,
+    };
+    Ok(BingoGame {
+        rules,
+        called,
+        cards,
+    })
+}
+
+/// Runs of consecutive non-blank lines, each line with its line number
+fn blocks<'a>(lines: impl Iterator<Item = (usize, &'a str)>) -> Vec<Vec<(usize, &'a str)>> {
+    let mut blocks = vec![Vec::new()];
+    for (n, line) in lines {
+        if !line.trim().is_empty() {
+            blocks.last_mut().unwrap().push((n, line));
+        } else if !blocks.last().unwrap().is_empty() {
+            blocks.push(Vec::new());
+        }
+    }
+    blocks.retain(|b| !b.is_empty());
+    blocks
+}
+
+/// The whitespace separated words of `line`, each with the column it starts at
+fn words(line: &str) -> impl Iterator<Item = (usize, &str)> {
+    line.split_whitespace()
+        .map(move |w| (w.as_ptr() as usize - line.as_ptr() as usize + 1, w))
+}
+
+/// One card of `height` rows with `width` distinct numbers each
+fn parse_card(block: &[(usize, &str)], width: usize, height: usize) -> Result<Vec<u32>> {
+    if block.len() != height {
+        anyhow::bail!("expected {} rows, found {}", height, block.len());
+    }
+    let mut seen: HashMap<u32, (usize, usize)> = HashMap::new();
+    let mut card = Vec::with_capacity(width * height);
+    for (n, line) in block {
+        let row = words(line)
+            .map(|(col, num)| {
+                parse_number(num)
+                    .map(|x| (col, x))
+                    .with_context(|| format!("line {} column {}", n, col))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if row.len() != width {
+            anyhow::bail!(
+                "line {}: expected {} numbers, found {}",
+                n,
+                width,
+                row.len()
+            );
+        }
+        for (col, x) in row {
+            if let Some((first_n, first_col)) = seen.insert(x, (*n, col)) {
+                anyhow::bail!(
+                    "line {} column {}: {} is already at line {} column {}",
+                    n,
+                    col,
+                    x,
+                    first_n,
+                    first_col
+                );
+            }
+            card.push(x);
+        }
+    }
+    Ok(card)
+}
+
+fn parse_number(num: &str) -> Result<u32> {
+    num.parse::<u32>()
+        .with_context(|| format!("could not parse number: {:?}", num))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::challenge::answers::expected;
     const INPUT: &str = include_str!("../../../input/day4");
     const EX: &str = include_str!("../../../input/day4_ex");
 
     #[test]
     fn verify_p1() {
-        assert_eq!(part1(INPUT).unwrap().as_str(), "35711")
+        assert_eq!(
+            part1(&parse(INPUT).unwrap()).unwrap().to_string(),
+            expected(4, "day4", 1)
+        )
     }
     #[test]
     fn verify_p2() {
-        assert_eq!(part2(INPUT).unwrap().as_str(), "5586")
+        assert_eq!(
+            part2(&parse(INPUT).unwrap()).unwrap().to_string(),
+            expected(4, "day4", 2)
+        )
     }
     #[test]
     fn check_p1_ex() {
-        assert_eq!(part1(EX).unwrap().as_str(), "4512")
+        assert_eq!(
+            part1(&parse(EX).unwrap()).unwrap().to_string(),
+            expected(4, "day4_ex", 1)
+        )
     }
     #[test]
     fn check_p2_ex() {
-        assert_eq!(part2(EX).unwrap().as_str(), "1924")
+        assert_eq!(
+            part2(&parse(EX).unwrap()).unwrap().to_string(),
+            expected(4, "day4_ex", 2)
+        )
+    }
+    #[test]
+    fn infer_rules() {
+        let game = parse(EX).unwrap();
+        assert_eq!(
+            game.rules,
+            bingo::BingoRules {
+                width: 5,
+                height: 5,
+                numbers: 27
+            }
+        );
+        // three wide and two high, numbers past the old limit of 100
+        let game = parse("300,250,1000,7\n\n1 2 3\n4 5 6\n\n300 250 1000\n7 8 9\n").unwrap();
+        assert_eq!(game.rules.width, 3);
+        assert_eq!(game.rules.height, 2);
+        assert_eq!(game.rules.numbers, 1001);
+        assert_eq!(part1(&game).unwrap(), (7 + 8 + 9) * 1000);
+        assert!(parse("1,2,3\n").is_err());
+        // a huge number costs no more than a small one
+        let game = parse("4000000000,1,2\n\n1 2\n3 4\n").unwrap();
+        assert_eq!(game.rules.numbers, 4000000001);
+        assert_eq!(part1(&game).unwrap(), (3 + 4) * 2);
+        let game = parse("4294967295,3,4\n\n4294967295 2\n3 4\n").unwrap();
+        assert_eq!(part1(&game).unwrap(), (2 + 4) * 3);
+    }
+    #[test]
+    fn malformed_cards() {
+        let error = |input: &str| format!("{:#}", parse(input).unwrap_err());
+        // a trailing partial card
+        assert_eq!(
+            error("1,2\n\n1 2\n3 4\n\n5 6\n"),
+            "bad bingo card #1 at line 6: expected 2 rows, found 1"
+        );
+        // a missing row no longer shifts the cards after it
+        assert_eq!(
+            error("1,2\n\n1 2\n3 4\n\n5 6\n\n7 8\n9 10\n"),
+            "bad bingo card #1 at line 6: expected 2 rows, found 1"
+        );
+        assert_eq!(
+            error("1,2\n\n1 2\n3 4\n\n5 6\n7\n"),
+            "bad bingo card #1 at line 6: line 7: expected 2 numbers, found 1"
+        );
+        assert_eq!(
+            error("1,2\n\n1 2\n3 4\n\n5 6\n7 x8\n"),
+            "bad bingo card #1 at line 6: line 7 column 3: could not parse number: \"x8\": \
+             invalid digit found in string"
+        );
+        assert_eq!(
+            error("1,2\n\n 1  2\n 3  1\n"),
+            "bad bingo card #0 at line 3: line 4 column 5: 1 is already at line 3 column 2"
+        );
+        assert_eq!(error("1,2\n\n\n"), "no bingo cards");
+        // blank lines with stray whitespace still separate cards
+        let game = parse("1,2\n  \n1 2\n3 4\n \n\n5 6\n7 8\n\n").unwrap();
+        assert_eq!(game.cards, [[1, 2, 3, 4], [5, 6, 7, 8]]);
+
+        let mut mb = bingo::MultiBingo::default();
+        assert!(mb.add_card(&[1, 2, 3]).is_err());
+        assert!(mb
+            .add_card(&(0..25).map(|x| x % 24).collect::<Vec<_>>())
+            .is_err());
+        assert!(mb.add_card(&(0..25).collect::<Vec<_>>()).is_ok());
+    }
+    #[test]
+    fn number_out_of_range() {
+        let mut mb = bingo::MultiBingo::new(bingo::BingoRules {
+            width: 2,
+            height: 2,
+            numbers: 10,
+        });
+        mb.add_card(&[1, 2, 3, 4]).unwrap();
+        assert!(mb.add_card(&[5, 6, 7, 10]).is_err());
+        assert!(mb.call_number(10).is_err());
+        assert_eq!(mb.call_number(1).unwrap(), None);
+        assert_eq!(mb.call_number(2).unwrap(), Some(0));
     }
 }
@@ -0,0 +1,383 @@
+use super::{Line, LineType};
+use anyhow::Result;
+use aoc::Point;
+use std::collections::HashMap;
+
+/// How many lines cover each point
+pub type HitMap = HashMap<Point, u32>;
+
+/// Which kinds of line to keep
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineFilter {
+    pub horizontal: bool,
+    pub vertical: bool,
+    /// At 45 degrees
+    pub diagonal: bool,
+    /// Any other slope
+    pub other: bool,
+}
+
+impl LineFilter {
+    pub const ALL: LineFilter = LineFilter {
+        horizontal: true,
+        vertical: true,
+        diagonal: true,
+        other: true,
+    };
+    /// Only the lines part 1 considers
+    pub const STRAIGHT: LineFilter = LineFilter {
+        horizontal: true,
+        vertical: true,
+        diagonal: false,
+        other: false,
+    };
+    const NONE: LineFilter = LineFilter {
+        horizontal: false,
+        vertical: false,
+        diagonal: false,
+        other: false,
+    };
+
+    pub fn accepts(&self, line: &Line) -> bool {
+        match line.line_type() {
+            LineType::Horizontal => self.horizontal,
+            LineType::Vertical => self.vertical,
+            LineType::Diagonal => self.diagonal,
+            LineType::Other => self.other,
+        }
+    }
+}
+
+impl std::str::FromStr for LineFilter {
+    type Err = anyhow::Error;
+
+    /// Comma separated kinds of line, or `straight` or `all`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut filter = LineFilter::NONE;
+        for kind in s.split(',') {
+            match kind.trim() {
+                "horizontal" => filter.horizontal = true,
+                "vertical" => filter.vertical = true,
+                "diagonal" => filter.diagonal = true,
+                "other" => filter.other = true,
+                "straight" => {
+                    filter.horizontal = true;
+                    filter.vertical = true;
+                }
+                "all" => filter = LineFilter::ALL,
+                _ => anyhow::bail!("unknown kind of line: {:?}", kind),
+            }
+        }
+        Ok(filter)
+    }
+}
+
+/// Most points along the lines that are counted one by one, to bound memory
+const MAX_SPARSE_POINTS: u128 = 1 << 24;
+
+/// Count how many of `segments` cover each point, visiting every point on
+/// them. Fails before visiting a line that would take the total past
+/// `MAX_SPARSE_POINTS`.
+pub fn hit_map(segments: impl Iterator<Item = Line>, filter: LineFilter) -> Result<HitMap> {
+    let mut hitmap = HitMap::new();
+    let mut points = 0;
+    for line in segments.filter(|l| filter.accepts(l)) {
+        points += line_length(&line);
+        if points > MAX_SPARSE_POINTS {
+            anyhow::bail!(
+                "the lines have too many points to count one by one, the limit is {}",
+                MAX_SPARSE_POINTS
+            );
+        }
+        for p in line.all_points() {
+            *hitmap.entry(p).or_default() += 1;
+        }
+    }
+    Ok(hitmap)
+}
+
+/// Number of points covered by at least `threshold` lines
+pub fn count_at_least(hits: &HitMap, threshold: u32) -> usize {
+    hits.values().filter(|c| **c >= threshold).count()
+}
+
+/// The smallest rectangle holding some points, inclusive
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl Bounds {
+    pub fn of(mut points: impl Iterator<Item = Point>) -> Option<Bounds> {
+        let first = points.next()?;
+        Some(points.fold(
+            Bounds {
+                min: first,
+                max: first,
+            },
+            |b, p| Bounds {
+                min: Point::new(b.min.x.min(p.x), b.min.y.min(p.y)),
+                max: Point::new(b.max.x.max(p.x), b.max.y.max(p.y)),
+            },
+        ))
+    }
+    /// Points across, if that fits in an `i64`
+    pub fn width(&self) -> Option<i64> {
+        self.max.x.checked_sub(self.min.x)?.checked_add(1)
+    }
+    /// Points down, if that fits in an `i64`
+    pub fn height(&self) -> Option<i64> {
+        self.max.y.checked_sub(self.min.y)?.checked_add(1)
+    }
+    /// Number of points inside, which may not fit in an `i64`
+    fn area(&self) -> u128 {
+        let side = |min: i64, max: i64| (max as i128 - min as i128 + 1) as u128;
+        side(self.min.x, self.max.x) * side(self.min.y, self.max.y)
+    }
+    /// Every point, a row at a time from the top
+    pub fn rows(&self) -> impl Iterator<Item = impl Iterator<Item = Point>> {
+        let Bounds { min, max } = *self;
+        (min.y..=max.y).map(move |y| (min.x..=max.x).map(move |x| Point::new(x, y)))
+    }
+}
+
+/// Dense grids larger than this are never chosen, to bound memory
+const MAX_DENSE_CELLS: u128 = 1 << 26;
+/// A dense grid is worth it while it has at most this many cells for each
+/// point along the lines
+const DENSE_CELLS_PER_POINT: u128 = 16;
+
+/// How to store the number of lines covering each point
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Backend {
+    /// Dense if the lines are packed closely enough, otherwise sparse
+    Auto,
+    /// A flat array over the bounding box of the lines
+    Dense,
+    /// Only the points that are covered, in a hash map
+    Sparse,
+}
+
+impl std::str::FromStr for Backend {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "auto" => Backend::Auto,
+            "dense" => Backend::Dense,
+            "sparse" => Backend::Sparse,
+            _ => anyhow::bail!("unknown backend: {:?}", s),
+        })
+    }
+}
+
+impl Backend {
+    /// Resolve `Auto` by comparing the bounding box of `lines` against the
+    /// number of points on them
+    pub fn choose(self, lines: &[Line]) -> Backend {
+        if self != Backend::Auto {
+            return self;
+        }
+        let area = match line_bounds(lines) {
+            Some(bounds) => bounds.area(),
+            None => return Backend::Sparse,
+        };
+        let length = lines.iter().map(line_length).sum::<u128>();
+        if area <= MAX_DENSE_CELLS && area <= length * DENSE_CELLS_PER_POINT {
+            Backend::Dense
+        } else {
+            Backend::Sparse
+        }
+    }
+}
+
+/// The smallest rectangle holding every point on `lines`
+pub fn line_bounds(lines: &[Line]) -> Option<Bounds> {
+    Bounds::of(lines.iter().flat_map(|l| [l.start, l.end]))
+}
+
+/// Number of lattice points on `line`, ends included
+fn line_length(line: &Line) -> u128 {
+    u128::from(line.steps) + 1
+}
+
+/// The most lines a dense grid can count at one point
+pub const MAX_DENSE_COUNT: u32 = u16::MAX as u32;
+
+/// Counts for every point in a rectangle, row by row. Counts stop at
+/// `MAX_DENSE_COUNT`.
+#[derive(Debug, Clone)]
+struct Grid {
+    bounds: Bounds,
+    width: usize,
+    counts: Vec<u16>,
+}
+
+impl Grid {
+    /// A grid over the bounding box of `lines`, as long as it has no more
+    /// than `MAX_DENSE_CELLS` cells
+    fn new(lines: &[Line], bounds: Bounds) -> Result<Grid> {
+        let area = bounds.area();
+        if area > MAX_DENSE_CELLS {
+            anyhow::bail!(
+                "{} cells are too many for a dense map, the limit is {}",
+                area,
+                MAX_DENSE_CELLS
+            );
+        }
+        // both sides are at most the area, which is small
+        let width = (bounds.max.x as i128 - bounds.min.x as i128 + 1) as usize;
+        let mut grid = Grid {
+            bounds,
+            width,
+            counts: vec![0; area as usize],
+        };
+        for p in lines.iter().flat_map(|l| l.all_points()) {
+            let idx = grid.index(p).expect("lines are inside their bounds");
+            grid.counts[idx] = grid.counts[idx].saturating_add(1);
+        }
+        Ok(grid)
+    }
+
+    fn index(&self, p: Point) -> Option<usize> {
+        let Bounds { min, max } = self.bounds;
+        if p.x < min.x || p.x > max.x || p.y < min.y || p.y > max.y {
+            return None;
+        }
+        Some((p.y - min.y) as usize * self.width + (p.x - min.x) as usize)
+    }
+
+    fn get(&self, p: Point) -> u32 {
+        self.index(p).map_or(0, |idx| self.counts[idx] as u32)
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Hits {
+    Dense(Grid),
+    Sparse(HitMap),
+}
+
+/// The vent lines together with how many of them cover each point
+#[derive(Debug, Clone)]
+pub struct VentField {
+    lines: Vec<Line>,
+    hits: Hits,
+}
+
+impl TryFrom<Vec<Line>> for VentField {
+    type Error = anyhow::Error;
+
+    fn try_from(lines: Vec<Line>) -> Result<VentField> {
+        VentField::filtered(lines, LineFilter::ALL)
+    }
+}
+
+impl VentField {
+    /// A field of only the `lines` that `filter` accepts, on whichever
+    /// backend suits them. Fails if they are too long to count sparsely.
+    pub fn filtered(mut lines: Vec<Line>, filter: LineFilter) -> Result<VentField> {
+        lines.retain(|l| filter.accepts(l));
+        let grid = match (Backend::Auto.choose(&lines), line_bounds(&lines)) {
+            (Backend::Dense, Some(bounds)) => Grid::new(&lines, bounds).ok(),
+            _ => None,
+        };
+        VentField::with_grid(lines, grid)
+    }
+
+    /// A field of only the `lines` that `filter` accepts, stored by
+    /// `backend`. Fails if the lines are too large for it.
+    pub fn with_backend(
+        mut lines: Vec<Line>,
+        filter: LineFilter,
+        backend: Backend,
+    ) -> Result<VentField> {
+        if backend == Backend::Auto {
+            return VentField::filtered(lines, filter);
+        }
+        lines.retain(|l| filter.accepts(l));
+        let grid = match (backend, line_bounds(&lines)) {
+            (Backend::Dense, Some(bounds)) => Some(Grid::new(&lines, bounds)?),
+            _ => None,
+        };
+        VentField::with_grid(lines, grid)
+    }
+
+    /// Count on `grid` if there is one, otherwise only the covered points
+    fn with_grid(lines: Vec<Line>, grid: Option<Grid>) -> Result<VentField> {
+        let hits = match grid {
+            Some(grid) => Hits::Dense(grid),
+            None => Hits::Sparse(hit_map(lines.iter().cloned(), LineFilter::ALL)?),
+        };
+        Ok(VentField { lines, hits })
+    }
+
+    pub fn lines(&self) -> &[Line] {
+        &self.lines
+    }
+
+    /// Which backend the counts ended up in
+    pub fn backend(&self) -> Backend {
+        match self.hits {
+            Hits::Dense(_) => Backend::Dense,
+            Hits::Sparse(_) => Backend::Sparse,
+        }
+    }
+
+    /// The smallest rectangle holding every covered point
+    pub fn bounds(&self) -> Option<Bounds> {
+        match &self.hits {
+            Hits::Dense(grid) => Some(grid.bounds),
+            Hits::Sparse(hits) => Bounds::of(hits.keys().copied()),
+        }
+    }
+
+    /// How many lines cover `point`
+    pub fn hits_at(&self, point: Point) -> u32 {
+        match &self.hits {
+            Hits::Dense(grid) => grid.get(point),
+            Hits::Sparse(hits) => hits.get(&point).copied().unwrap_or(0),
+        }
+    }
+
+    /// Every point covered by at least one line, and how many
+    fn covered(&self) -> Box<dyn Iterator<Item = (Point, u32)> + '_> {
+        match &self.hits {
+            Hits::Dense(grid) => Box::new(
+                grid.bounds
+                    .rows()
+                    .flatten()
+                    .zip(&grid.counts)
+                    .filter(|(_, c)| **c > 0)
+                    .map(|(p, c)| (p, *c as u32)),
+            ),
+            Hits::Sparse(hits) => Box::new(hits.iter().map(|(p, c)| (*p, *c))),
+        }
+    }
+
+    /// Number of points covered by at least `threshold` lines. On a dense
+    /// grid this is only exact up to `MAX_DENSE_COUNT`.
+    pub fn count_at_least(&self, threshold: u32) -> usize {
+        match &self.hits {
+            Hits::Dense(grid) => grid
+                .counts
+                .iter()
+                .filter(|c| **c as u32 >= threshold.max(1))
+                .count(),
+            Hits::Sparse(hits) => count_at_least(hits, threshold),
+        }
+    }
+
+    /// The lines that pass through `point`, in input order
+    pub fn lines_through(&self, point: Point) -> impl Iterator<Item = &Line> {
+        self.lines.iter().filter(move |l| l.contains(point))
+    }
+
+    /// The point covered by the most lines, the top-most then left-most of
+    /// any ties
+    pub fn densest(&self) -> Option<(Point, u32)> {
+        self.covered()
+            .min_by_key(|(p, c)| (std::cmp::Reverse(*c), p.y, p.x))
+    }
+}
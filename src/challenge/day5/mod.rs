@@ -1,38 +1,200 @@
+use super::{
+    input,
+    solution::{Day, Solution, Streaming},
+};
 use anyhow::{Context, Result};
 use aoc::Point;
-use std::collections::HashMap;
+use field::{Backend, LineFilter, VentField};
+use std::{
+    fs,
+    io::{self, BufRead, Write},
+    time::Instant,
+};
 
-pub fn part1(input: &str) -> Result<String> {
-    let overlaps = count_intersections(input, true)?;
-    Ok(format!("{:?}", overlaps))
+mod field;
+mod render;
+mod sweep;
+
+/// Points covered by at least this many lines are the puzzle's answer
+const OVERLAP: u32 = 2;
+
+pub struct Day5;
+
+pub const SOLUTION: Solution = Solution::streaming::<Day5>();
+
+impl Day for Day5 {
+    const DAY: u32 = 5;
+    const TITLE: &'static str = "Hydrothermal Venture";
+    const EXAMPLE: &'static str = include_str!("../../../input/day5_ex");
+
+    type Input = Vec<Line>;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        parse(input)
+    }
+    fn part1(segments: &Self::Input) -> Result<usize> {
+        part1(segments)
+    }
+    fn part2(segments: &Self::Input) -> Result<usize> {
+        part2(segments)
+    }
 }
 
-pub fn part2(input: &str) -> Result<String> {
-    let overlaps = count_intersections(input, false)?;
-    Ok(format!("{:?}", overlaps))
+/// Streaming rasterises each line as it is read, so only the hit map is kept
+impl Streaming for Day5 {
+    fn stream_part1(reader: &mut dyn BufRead) -> Result<usize> {
+        input::process_results(lines(reader), |segments| {
+            count_intersections(segments, LineFilter::STRAIGHT)
+        })?
+    }
+    fn stream_part2(reader: &mut dyn BufRead) -> Result<usize> {
+        input::process_results(lines(reader), |segments| {
+            count_intersections(segments, LineFilter::ALL)
+        })?
+    }
 }
 
-fn count_intersections(input: &str, filter_straight: bool) -> Result<usize> {
-    let segments = parse(input)?;
-    let mut hitmap: HashMap<Point, u32> = HashMap::new();
-    let hit_inc = |p: Point| {
-        let e = hitmap.entry(p).or_default();
-        *e += 1;
-    };
+pub fn part1(segments: &[Line]) -> Result<usize> {
+    count_overlaps(segments, LineFilter::STRAIGHT, Backend::Auto)
+}
+
+pub fn part2(segments: &[Line]) -> Result<usize> {
+    count_overlaps(segments, LineFilter::ALL, Backend::Auto)
+}
 
-    segments
+/// Points covered by at least two of the lines `filter` accepts. Closely
+/// packed lines are counted on a dense grid, anything sparser is swept
+/// without visiting every point.
+fn count_overlaps(segments: &[Line], filter: LineFilter, backend: Backend) -> Result<usize> {
+    let lines = segments
         .iter()
-        .filter(|l| l.line_type().filter_straight(filter_straight))
-        .flat_map(|l| l.all_points())
-        .for_each(hit_inc);
+        .filter(|l| filter.accepts(l))
+        .cloned()
+        .collect::<Vec<_>>();
+    match backend.choose(&lines) {
+        Backend::Sparse => Ok(sweep::count_overlaps(lines.into_iter())),
+        backend => VentField::with_backend(lines, LineFilter::ALL, backend)
+            .map(|field| field.count_at_least(OVERLAP)),
+    }
+}
+
+/// Query or draw the vent map for the `vents` subcommand. Any query replaces
+/// the drawing.
+pub fn vents(args: &clap::ArgMatches) -> Result<()> {
+    let data = input::load(
+        args.value_of("input"),
+        &SOLUTION,
+        args.is_present("example"),
+    )?
+    .data;
+    let filter = args
+        .value_of("lines")
+        .unwrap_or("all")
+        .parse::<LineFilter>()?;
+    let mut backend = args
+        .value_of("backend")
+        .unwrap_or("auto")
+        .parse::<Backend>()?;
+    let threshold = args
+        .value_of("threshold")
+        .map(|t| {
+            t.parse::<u32>()
+                .with_context(|| format!("could not parse number: {:?}", t))
+        })
+        .transpose()?;
+    if let Some(threshold) = threshold.filter(|t| *t > field::MAX_DENSE_COUNT) {
+        match backend {
+            Backend::Dense => anyhow::bail!(
+                "a dense map can not count to {}, the limit is {}",
+                threshold,
+                field::MAX_DENSE_COUNT
+            ),
+            _ => backend = Backend::Sparse,
+        }
+    }
+    let queried = threshold.is_some() || args.is_present("at") || args.is_present("densest");
+    let mut lines = parse(&data)?;
+    lines.retain(|l| filter.accepts(l));
+    let format = if queried {
+        None
+    } else {
+        let format = args
+            .value_of("format")
+            .unwrap_or("ascii")
+            .parse::<render::Format>()?;
+        render::check_size(field::line_bounds(&lines), format)?;
+        Some(format)
+    };
+
+    let start = Instant::now();
+    let field = VentField::with_backend(lines, LineFilter::ALL, backend)?;
+    log::info!(
+        "counted {} lines on a {:?} map in {:?}",
+        field.lines().len(),
+        field.backend(),
+        start.elapsed()
+    );
+
+    if let Some(threshold) = threshold {
+        println!(
+            "{} points are covered by at least {} lines",
+            field.count_at_least(threshold),
+            threshold
+        );
+    }
+    if let Some(point) = args.value_of("at") {
+        let point = parse_point(point)?;
+        println!(
+            "{} lines cover {},{}",
+            field.hits_at(point),
+            point.x,
+            point.y
+        );
+        for line in field.lines_through(point) {
+            println!("  {}", line);
+        }
+    }
+    if args.is_present("densest") {
+        match field.densest() {
+            Some((p, count)) => println!("{},{} is covered by {} lines", p.x, p.y, count),
+            None => println!("there are no vents"),
+        }
+    }
+    let format = match format {
+        Some(format) => format,
+        None => return Ok(()),
+    };
+    match args.value_of("output") {
+        Some(path) => {
+            let mut f = io::BufWriter::new(
+                fs::File::create(path)
+                    .with_context(|| format!("Unable to write path: {}", path))?,
+            );
+            render::write(&mut f, &field, format)?;
+            f.flush()?;
+        }
+        None => render::write(&mut io::stdout().lock(), &field, format)?,
+    }
+    Ok(())
+}
 
-    Ok(hitmap.values().filter(|c| **c >= 2).count())
+fn count_intersections(segments: impl Iterator<Item = Line>, filter: LineFilter) -> Result<usize> {
+    Ok(field::count_at_least(
+        &field::hit_map(segments, filter)?,
+        OVERLAP,
+    ))
 }
 
 fn parse(input: &str) -> Result<Vec<Line>> {
     input.lines().map(parse_line).collect()
 }
 
+fn lines(reader: &mut dyn BufRead) -> impl Iterator<Item = Result<Line>> + '_ {
+    input::lines(reader).map(|l| l.and_then(|l| parse_line(&l)))
+}
+
 fn parse_line(input: &str) -> Result<Line> {
     let mut segments = input.split(" -> ");
     let start_str = segments
@@ -43,7 +205,7 @@ fn parse_line(input: &str) -> Result<Line> {
         .ok_or_else(|| anyhow::anyhow!("could not parse points: {:?}", input))?;
     let start = parse_point(start_str)?;
     let end = parse_point(end_str)?;
-    Ok(Line { start, end })
+    Line::new(start, end)
 }
 
 fn parse_point(input: &str) -> Result<Point> {
@@ -67,97 +229,403 @@ fn parse_point(input: &str) -> Result<Point> {
 enum LineType {
     Horizontal,
     Vertical,
+    /// At 45 degrees
+    Diagonal,
     Other,
 }
 
-impl LineType {
-    fn is_stright(self) -> bool {
-        match self {
-            LineType::Horizontal => true,
-            LineType::Vertical => true,
-            LineType::Other => false,
-        }
-    }
-    fn filter_straight(self, do_filter: bool) -> bool {
-        !do_filter || self.is_stright()
-    }
-}
-
 #[derive(Debug, Clone)]
-struct Line {
+pub struct Line {
     start: Point,
     end: Point,
+    /// Smallest lattice step from `start` towards `end`
+    step: Point,
+    /// Number of `step`s from `start` to `end`
+    steps: u64,
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
 }
 
 impl Line {
+    /// A line through every lattice point from `start` to `end`, or an error
+    /// if its length does not fit in an `i64`
+    fn new(start: Point, end: Point) -> Result<Line> {
+        let too_long = || anyhow::anyhow!("line is too long: {:?} -> {:?}", start, end);
+        let dx = end.x.checked_sub(start.x).ok_or_else(too_long)?;
+        let dy = end.y.checked_sub(start.y).ok_or_else(too_long)?;
+        let steps = gcd(dx.unsigned_abs(), dy.unsigned_abs());
+        let step = match i64::try_from(steps).map_err(|_| too_long())? {
+            0 => Point::new(0, 0),
+            g => Point::new(dx / g, dy / g),
+        };
+        Ok(Line {
+            start,
+            end,
+            step,
+            steps,
+        })
+    }
+
     fn line_type(&self) -> LineType {
         if self.start.x == self.end.x {
             LineType::Vertical
         } else if self.start.y == self.end.y {
             LineType::Horizontal
+        } else if self.step.x.abs() == self.step.y.abs() {
+            LineType::Diagonal
         } else {
             LineType::Other
         }
     }
 
+    /// Whether `point` is one of the lattice points on the line
+    fn contains(&self, point: Point) -> bool {
+        let offset = |from: i64, to: i64| to as i128 - from as i128;
+        let (dx, dy) = (offset(self.start.x, point.x), offset(self.start.y, point.y));
+        let (sx, sy) = (self.step.x as i128, self.step.y as i128);
+        let t = match (sx, sy) {
+            (0, 0) => return dx == 0 && dy == 0,
+            (0, _) if dx == 0 && dy % sy == 0 => dy / sy,
+            (_, _) if sx != 0 && dx % sx == 0 && dx / sx * sy == dy => dx / sx,
+            _ => return false,
+        };
+        (0..=self.steps as i128).contains(&t)
+    }
+
     fn all_points(&self) -> LinePoints {
-        let dx = self.end.x - self.start.x;
-        let dy = self.end.y - self.start.y;
-        // Slopes will never be rationals, just +/- 0,1 & 1,0 & 1,1
-        let slope = Point::new(
-            if dx == 0 { 0 } else { dx / dx.abs() },
-            if dy == 0 { 0 } else { dy / dy.abs() },
-        );
         LinePoints {
             current: self.start,
-            target: self.end + slope,
-            slope,
+            step: self.step,
+            remaining: Some(self.steps),
         }
     }
 }
 
+impl std::fmt::Display for Line {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{},{} -> {},{}",
+            self.start.x, self.start.y, self.end.x, self.end.y
+        )
+    }
+}
+
+/// Lattice points along a line, including both ends
 #[derive(Debug)]
 struct LinePoints {
     current: Point,
-    target: Point,
-    slope: Point,
+    step: Point,
+    /// Steps left after the current point, `None` once it has been yielded
+    remaining: Option<u64>,
 }
 
 impl Iterator for LinePoints {
     type Item = Point;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.current == self.target {
-            None
+        let remaining = self.remaining?;
+        let ret = self.current;
+        if remaining == 0 {
+            self.remaining = None;
         } else {
-            let ret = self.current;
-            self.current += self.slope;
-            Some(ret)
+            self.current += self.step;
+            self.remaining = Some(remaining - 1);
         }
+        Some(ret)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::challenge::answers::expected;
     const INPUT: &str = include_str!("../../../input/day5");
     const EX: &str = include_str!("../../../input/day5_ex");
 
     #[test]
     fn verify_p1() {
-        assert_eq!(part1(INPUT).unwrap().as_str(), "6572")
+        assert_eq!(
+            part1(&parse(INPUT).unwrap()).unwrap().to_string(),
+            expected(5, "day5", 1)
+        )
     }
     #[test]
     fn verify_p2() {
-        assert_eq!(part2(INPUT).unwrap().as_str(), "21466")
+        assert_eq!(
+            part2(&parse(INPUT).unwrap()).unwrap().to_string(),
+            expected(5, "day5", 2)
+        )
     }
 
     #[test]
     fn check_p1_example() {
-        assert_eq!(part1(EX).unwrap().as_str(), "5")
+        assert_eq!(
+            part1(&parse(EX).unwrap()).unwrap().to_string(),
+            expected(5, "day5_ex", 1)
+        )
     }
     #[test]
     fn check_p2_example() {
-        assert_eq!(part2(EX).unwrap().as_str(), "12")
+        assert_eq!(
+            part2(&parse(EX).unwrap()).unwrap().to_string(),
+            expected(5, "day5_ex", 2)
+        )
+    }
+    #[test]
+    fn general_slopes() {
+        let points = |l: &str| parse_line(l).unwrap().all_points().collect::<Vec<_>>();
+        assert_eq!(
+            points("0,0 -> 4,2"),
+            vec![Point::new(0, 0), Point::new(2, 1), Point::new(4, 2)]
+        );
+        assert_eq!(
+            points("9,7 -> 0,1"),
+            vec![
+                Point::new(9, 7),
+                Point::new(6, 5),
+                Point::new(3, 3),
+                Point::new(0, 1)
+            ]
+        );
+        assert_eq!(
+            points("1,5 -> 2,9"),
+            vec![Point::new(1, 5), Point::new(2, 9)]
+        );
+        assert_eq!(points("3,3 -> 3,3"), vec![Point::new(3, 3)]);
+        assert_eq!(points("0,0 -> 0,3").len(), 4);
+    }
+    #[test]
+    fn line_too_long() {
+        assert!(parse_line("-9223372036854775808,0 -> 9223372036854775807,0").is_err());
+        assert!(parse_line("-9223372036854775808,0 -> 0,0").is_err());
+        assert!(parse_line("-9223372036854775807,0 -> 0,0").is_ok());
+    }
+    #[test]
+    fn sweep_matches_hitmap() {
+        for input in [INPUT, EX] {
+            let lines = parse(input).unwrap();
+            for filter in [LineFilter::STRAIGHT, LineFilter::ALL] {
+                let filtered = lines
+                    .iter()
+                    .filter(|l| filter.accepts(l))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                assert_eq!(
+                    sweep::count_overlaps(filtered.iter().cloned()),
+                    count_intersections(filtered.into_iter(), LineFilter::ALL).unwrap()
+                );
+            }
+        }
+        let tricky = parse(
+            "0,0 -> 4,2\n2,1 -> 6,3\n0,2 -> 4,0\n4,0 -> 4,6\n4,4 -> 4,8\n\
+             0,4 -> 8,4\n3,4 -> 5,4\n5,5 -> 5,5\n5,5 -> 9,9\n9,9 -> 0,0\n1,9 -> 9,1",
+        )
+        .unwrap();
+        assert_eq!(
+            sweep::count_overlaps(tricky.iter().cloned()),
+            count_intersections(tricky.into_iter(), LineFilter::ALL).unwrap()
+        );
+    }
+    #[test]
+    fn sweep_huge_coordinates() {
+        let lines = parse(
+            "0,0 -> 1000000000000,0\n\
+             500000000000,0 -> 2000000000000,0\n\
+             7,-1000000000000 -> 7,1000000000000\n\
+             -1000000000000,-1000000000000 -> 1000000000000,1000000000000",
+        )
+        .unwrap();
+        // the collinear overlap, plus 0,0 7,0 and 7,7 where the lines cross
+        assert_eq!(sweep::count_overlaps(lines.into_iter()), 500000000001 + 3);
+    }
+    #[test]
+    fn draw_example() {
+        let field = VentField::filtered(parse(EX).unwrap(), LineFilter::STRAIGHT).unwrap();
+        let mut out = Vec::new();
+        render::write_ascii(&mut out, &field).unwrap();
+        // the straight lines only, as drawn in the puzzle
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            ".......1..\n\
+             ..1....1..\n\
+             ..1....1..\n\
+             .......1..\n\
+             .112111211\n\
+             ..........\n\
+             ..........\n\
+             ..........\n\
+             ..........\n\
+             222111....\n"
+        );
+        let field =
+            VentField::with_backend(parse(EX).unwrap(), LineFilter::ALL, Backend::Sparse).unwrap();
+        let mut out = Vec::new();
+        render::write_ascii(&mut out, &field).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "1.1....11.\n\
+             .111...2..\n\
+             ..2.1.111.\n\
+             ...1.2.2..\n\
+             .112313211\n\
+             ...1.2....\n\
+             ..1...1...\n\
+             .1.....1..\n\
+             1.......1.\n\
+             222111....\n"
+        );
+    }
+    #[test]
+    fn draw_image() {
+        let hits = VentField::try_from(parse(EX).unwrap()).unwrap();
+        let mut out = Vec::new();
+        render::write(&mut out, &hits, render::Format::Ppm).unwrap();
+        assert!(out.starts_with(b"P6\n10 10\n255\n"));
+        assert_eq!(out.len(), "P6\n10 10\n255\n".len() + 10 * 10 * 3);
+        let mut out = Vec::new();
+        render::write(&mut out, &hits, render::Format::Pgm).unwrap();
+        assert_eq!(out.len(), "P5\n10 10\n255\n".len() + 10 * 10);
+        assert!(render::write(
+            &mut out,
+            &VentField::try_from(Vec::new()).unwrap(),
+            render::Format::Ascii
+        )
+        .is_err());
+
+        let far_apart =
+            VentField::try_from(parse("0,0 -> 0,0\n100000,100000 -> 100000,100000").unwrap())
+                .unwrap();
+        let e = render::write(&mut out, &far_apart, render::Format::Pgm).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "100001x100001 is too large to draw as an image, the limit is 67108864 points"
+        );
+        assert!(render::write(&mut out, &far_apart, render::Format::Ascii).is_err());
+        let extreme = VentField::try_from(
+            parse(
+                "-9223372036854775807,0 -> -9223372036854775807,0\n\
+                 9223372036854775807,0 -> 9223372036854775807,0",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert!(render::write(&mut out, &extreme, render::Format::Ppm).is_err());
+    }
+    #[test]
+    fn query_field() {
+        let field = VentField::try_from(parse(EX).unwrap()).unwrap();
+        assert_eq!(field.count_at_least(OVERLAP), 12);
+        assert_eq!(field.count_at_least(3), 2);
+        assert_eq!(field.densest(), Some((Point::new(4, 4), 3)));
+        let through = field
+            .lines_through(Point::new(4, 4))
+            .map(|l| l.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(through, ["8,0 -> 0,8", "9,4 -> 3,4", "0,0 -> 8,8"]);
+        assert_eq!(field.lines_through(Point::new(9, 9)).count(), 0);
+        assert_eq!(field.hits_at(Point::new(9, 9)), 0);
+
+        let diagonal =
+            VentField::filtered(parse(EX).unwrap(), "diagonal".parse().unwrap()).unwrap();
+        assert_eq!(diagonal.lines().len(), 4);
+        assert_eq!(diagonal.densest(), Some((Point::new(5, 3), 2)));
+        let straight = "horizontal, vertical".parse::<LineFilter>().unwrap();
+        assert_eq!(straight, LineFilter::STRAIGHT);
+        assert!("curved".parse::<LineFilter>().is_err());
+    }
+    #[test]
+    fn dense_matches_sparse() {
+        for filter in [LineFilter::STRAIGHT, LineFilter::ALL] {
+            let lines = parse(INPUT).unwrap();
+            let dense = VentField::with_backend(lines.clone(), filter, Backend::Dense).unwrap();
+            let sparse = VentField::with_backend(lines.clone(), filter, Backend::Sparse).unwrap();
+            assert_eq!(dense.backend(), Backend::Dense);
+            assert_eq!(sparse.backend(), Backend::Sparse);
+            for threshold in 0..5 {
+                assert_eq!(
+                    dense.count_at_least(threshold),
+                    sparse.count_at_least(threshold)
+                );
+            }
+            assert_eq!(dense.densest(), sparse.densest());
+            assert_eq!(dense.bounds(), sparse.bounds());
+            assert_eq!(
+                count_overlaps(&lines, filter, Backend::Dense).unwrap(),
+                count_overlaps(&lines, filter, Backend::Sparse).unwrap()
+            );
+        }
+    }
+    #[test]
+    fn choose_backend() {
+        assert_eq!(Backend::Auto.choose(&parse(INPUT).unwrap()), Backend::Dense);
+        assert_eq!(Backend::Auto.choose(&[]), Backend::Sparse);
+        let spread = parse("0,0 -> 0,9\n1000,1000 -> 1000,1009").unwrap();
+        assert_eq!(Backend::Auto.choose(&spread), Backend::Sparse);
+        assert_eq!(Backend::Dense.choose(&spread), Backend::Dense);
+        let huge = parse("0,0 -> 1000000000000,1000000000000").unwrap();
+        assert_eq!(Backend::Auto.choose(&huge), Backend::Sparse);
+        assert!(VentField::with_backend(huge.clone(), LineFilter::ALL, Backend::Dense).is_err());
+        assert!(count_overlaps(&huge, LineFilter::ALL, Backend::Dense).is_err());
+        let wide = parse("0,0 -> 100000,100000").unwrap();
+        let field = VentField::with_backend(wide, LineFilter::ALL, Backend::Auto).unwrap();
+        assert_eq!(field.backend(), Backend::Sparse);
+    }
+    #[test]
+    fn too_long_to_count() {
+        let long = parse(
+            "0,0 -> 1000000000000,0
+0,1 -> 0,5",
+        )
+        .unwrap();
+        assert!(VentField::try_from(long.clone()).is_err());
+        assert!(VentField::with_backend(long.clone(), LineFilter::ALL, Backend::Sparse).is_err());
+        assert!(Day5::stream_part2(&mut "0,0 -> 1000000000000,0\n".as_bytes()).is_err());
+        // the sweep has no such limit
+        assert_eq!(
+            count_overlaps(&long, LineFilter::ALL, Backend::Auto).unwrap(),
+            0
+        );
+        assert!(render::check_size(field::line_bounds(&long), render::Format::Ppm).is_err());
+        let short = VentField::filtered(long, "vertical".parse().unwrap()).unwrap();
+        assert_eq!(short.count_at_least(1), 5);
+    }
+    #[test]
+    fn line_contains() {
+        let line = parse_line("9,7 -> 0,1").unwrap();
+        for p in line.all_points() {
+            assert!(line.contains(p));
+        }
+        assert!(!line.contains(Point::new(12, 9)));
+        assert!(!line.contains(Point::new(7, 6)));
+        assert!(!line.contains(Point::new(-3, -1)));
+        let vertical = parse_line("4,0 -> 4,6").unwrap();
+        assert!(vertical.contains(Point::new(4, 3)));
+        assert!(!vertical.contains(Point::new(4, 7)));
+        assert!(!vertical.contains(Point::new(5, 3)));
+        let point = parse_line("3,3 -> 3,3").unwrap();
+        assert!(point.contains(Point::new(3, 3)));
+        assert!(!point.contains(Point::new(3, 4)));
+    }
+    #[test]
+    fn verify_streaming() {
+        assert_eq!(
+            Day5::stream_part1(&mut INPUT.as_bytes())
+                .unwrap()
+                .to_string(),
+            expected(5, "day5", 1)
+        );
+        assert_eq!(
+            Day5::stream_part2(&mut INPUT.as_bytes())
+                .unwrap()
+                .to_string(),
+            expected(5, "day5", 2)
+        );
     }
 }
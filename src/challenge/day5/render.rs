@@ -0,0 +1,119 @@
+use super::field::{Bounds, VentField};
+use anyhow::Result;
+use std::io::Write;
+
+/// Larger maps than this are refused as text, use an image instead
+const MAX_ASCII_CELLS: i64 = 1_000_000;
+/// Larger maps than this are refused as images
+const MAX_IMAGE_CELLS: i64 = 1 << 26;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Ascii,
+    Pgm,
+    Ppm,
+}
+
+impl std::str::FromStr for Format {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "ascii" => Format::Ascii,
+            "pgm" => Format::Pgm,
+            "ppm" => Format::Ppm,
+            _ => anyhow::bail!("unknown image format: {:?}", s),
+        })
+    }
+}
+
+/// The area to draw, and its width and height, as long as it has no more
+/// than `max_cells` points
+fn area(bounds: Option<Bounds>, max_cells: i64, medium: &str) -> Result<(Bounds, i64, i64)> {
+    let bounds = bounds.ok_or_else(|| anyhow::anyhow!("there are no vents to draw"))?;
+    let too_large = || anyhow::anyhow!("{:?} is too large to draw as {}", bounds, medium);
+    let width = bounds.width().ok_or_else(too_large)?;
+    let height = bounds.height().ok_or_else(too_large)?;
+    match width.checked_mul(height) {
+        Some(cells) if cells <= max_cells => Ok((bounds, width, height)),
+        _ => anyhow::bail!(
+            "{}x{} is too large to draw as {}, the limit is {} points",
+            width,
+            height,
+            medium,
+            max_cells
+        ),
+    }
+}
+
+/// The diagram from the puzzle: `.` where there are no lines, otherwise the
+/// number of lines, with `#` for more than 9
+pub fn write_ascii(out: &mut dyn Write, field: &VentField) -> Result<()> {
+    let (bounds, _, _) = area(field.bounds(), MAX_ASCII_CELLS, "text")?;
+    for row in bounds.rows() {
+        let line = row
+            .map(|p| match field.hits_at(p) {
+                0 => '.',
+                c @ 1..=9 => char::from_digit(c, 10).unwrap(),
+                _ => '#',
+            })
+            .collect::<String>();
+        writeln!(out, "{}", line)?;
+    }
+    Ok(())
+}
+
+/// Black for no vents, blue for a single line, then yellow to red as the
+/// overlaps get denser
+fn heat(count: u32, max: u32) -> [u8; 3] {
+    match count {
+        0 => [0, 0, 0],
+        1 => [40, 60, 160],
+        _ => {
+            let scale = if max > 2 {
+                (count - 2) as f64 / (max - 2) as f64
+            } else {
+                0.0
+            };
+            [255, (255.0 * (1.0 - scale)) as u8, 0]
+        }
+    }
+}
+
+fn write_image(out: &mut dyn Write, field: &VentField, format: Format) -> Result<()> {
+    let (bounds, width, height) = area(field.bounds(), MAX_IMAGE_CELLS, "an image")?;
+    let max = field.densest().map_or(0, |(_, count)| count);
+    let magic = if format == Format::Pgm { "P5" } else { "P6" };
+    write!(out, "{}\n{} {}\n255\n", magic, width, height)?;
+    for row in bounds.rows() {
+        let pixels = row
+            .flat_map(|p| {
+                let count = field.hits_at(p);
+                match format {
+                    Format::Pgm => vec![(count * 255 / max.max(1)) as u8],
+                    _ => heat(count, max).to_vec(),
+                }
+            })
+            .collect::<Vec<_>>();
+        out.write_all(&pixels)?;
+    }
+    Ok(())
+}
+
+/// Fail if vents within `bounds` are too large to draw as `format`, before
+/// going to the trouble of counting them
+pub fn check_size(bounds: Option<Bounds>, format: Format) -> Result<()> {
+    match format {
+        Format::Ascii => area(bounds, MAX_ASCII_CELLS, "text"),
+        Format::Pgm | Format::Ppm => area(bounds, MAX_IMAGE_CELLS, "an image"),
+    }
+    .map(|_| ())
+}
+
+/// Draw `field` over the smallest rectangle containing every vent
+pub fn write(out: &mut dyn Write, field: &VentField, format: Format) -> Result<()> {
+    match format {
+        Format::Ascii => write_ascii(out, field),
+        Format::Pgm | Format::Ppm => write_image(out, field, format),
+    }
+}
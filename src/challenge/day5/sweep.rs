@@ -0,0 +1,190 @@
+use super::Line;
+use std::collections::HashMap;
+
+type Interval = (i128, i128);
+
+/// Every line that lies along the same infinite line, measured in steps of
+/// its shared primitive direction
+#[derive(Debug)]
+struct Carrier {
+    direction: (i128, i128),
+    /// A point on the carrier, and its position along it
+    reference: (i128, i128),
+    reference_t: i128,
+    intervals: Vec<Interval>,
+}
+
+impl Carrier {
+    fn point_at(&self, t: i128) -> (i128, i128) {
+        let offset = t - self.reference_t;
+        (
+            self.reference.0 + offset * self.direction.0,
+            self.reference.1 + offset * self.direction.1,
+        )
+    }
+}
+
+/// The direction of `line` reduced to lowest terms, pointing right (or up)
+fn direction(line: &Line) -> (i128, i128) {
+    let (x, y) = (line.step.x as i128, line.step.y as i128);
+    if line.steps == 0 {
+        (1, 0)
+    } else if x < 0 || (x == 0 && y < 0) {
+        (-x, -y)
+    } else {
+        (x, y)
+    }
+}
+
+/// Position of a lattice point along a carrier with `direction`. Neighbouring
+/// lattice points on the carrier are exactly 1 apart.
+fn position(point: (i128, i128), direction: (i128, i128)) -> i128 {
+    let dot = point.0 * direction.0 + point.1 * direction.1;
+    dot.div_euclid(direction.0 * direction.0 + direction.1 * direction.1)
+}
+
+/// Sweep the interval ends, returning the stretches covered at least once
+/// and at least twice
+fn coverage(intervals: &[Interval]) -> (Vec<Interval>, Vec<Interval>) {
+    let mut events = intervals
+        .iter()
+        .flat_map(|(start, end)| [(*start, 1), (*end + 1, -1)])
+        .collect::<Vec<_>>();
+    events.sort_unstable();
+
+    let mut union: Vec<Interval> = Vec::new();
+    let mut overlap: Vec<Interval> = Vec::new();
+    let mut count = 0;
+    let mut idx = 0;
+    while idx < events.len() {
+        let pos = events[idx].0;
+        while idx < events.len() && events[idx].0 == pos {
+            count += events[idx].1;
+            idx += 1;
+        }
+        let next = match events.get(idx) {
+            Some((next, _)) => *next,
+            None => break,
+        };
+        for (min, stretches) in [(1, &mut union), (2, &mut overlap)] {
+            if count < min {
+                continue;
+            }
+            match stretches.last_mut() {
+                Some(last) if last.1 + 1 == pos => last.1 = next - 1,
+                _ => stretches.push((pos, next - 1)),
+            }
+        }
+    }
+    (union, overlap)
+}
+
+fn contains(stretches: &[Interval], t: i128) -> bool {
+    let idx = stretches.partition_point(|(_, end)| *end < t);
+    matches!(stretches.get(idx), Some((start, _)) if *start <= t)
+}
+
+fn cross(a: (i128, i128), b: (i128, i128)) -> i128 {
+    a.0 * b.1 - a.1 * b.0
+}
+
+/// A covered stretch of one carrier, as a start point and a length in steps
+struct Stretch {
+    carrier: usize,
+    start: (i128, i128),
+    direction: (i128, i128),
+    len: i128,
+}
+
+/// The lattice point where two non-parallel stretches cross, if there is one
+fn crossing(a: &Stretch, b: &Stretch) -> Option<(i128, i128)> {
+    let det = cross(a.direction, b.direction);
+    if det == 0 {
+        return None;
+    }
+    let between = (b.start.0 - a.start.0, b.start.1 - a.start.1);
+    let s = cross(between, b.direction);
+    let t = cross(between, a.direction);
+    if s % det != 0 || t % det != 0 {
+        return None;
+    }
+    let (s, t) = (s / det, t / det);
+    if (0..=a.len).contains(&s) && (0..=b.len).contains(&t) {
+        Some((a.start.0 + s * a.direction.0, a.start.1 + s * a.direction.1))
+    } else {
+        None
+    }
+}
+
+/// Count lattice points covered by at least two lines, without visiting
+/// every point on them.
+///
+/// Lines on the same carrier are merged by a sweep over their end points,
+/// which finds every overlap between collinear lines. Any other overlap is
+/// a single point where two carriers cross, found by checking each pair of
+/// merged stretches. The cost depends on the number of lines, not their
+/// length.
+pub fn count_overlaps(segments: impl Iterator<Item = Line>) -> usize {
+    let mut carriers: Vec<Carrier> = Vec::new();
+    let mut by_key: HashMap<(i128, i128, i128), usize> = HashMap::new();
+    for line in segments {
+        let direction = direction(&line);
+        let start = (line.start.x as i128, line.start.y as i128);
+        let end = (line.end.x as i128, line.end.y as i128);
+        let offset = cross(direction, start);
+        let idx = *by_key
+            .entry((direction.0, direction.1, offset))
+            .or_insert_with(|| {
+                carriers.push(Carrier {
+                    direction,
+                    reference: start,
+                    reference_t: position(start, direction),
+                    intervals: Vec::new(),
+                });
+                carriers.len() - 1
+            });
+        let (a, b) = (position(start, direction), position(end, direction));
+        carriers[idx].intervals.push((a.min(b), a.max(b)));
+    }
+
+    let mut overlaps = Vec::with_capacity(carriers.len());
+    let mut stretches = Vec::new();
+    for (idx, carrier) in carriers.iter().enumerate() {
+        let (union, overlap) = coverage(&carrier.intervals);
+        stretches.extend(union.into_iter().map(|(start, end)| Stretch {
+            carrier: idx,
+            start: carrier.point_at(start),
+            direction: carrier.direction,
+            len: end - start,
+        }));
+        overlaps.push(overlap);
+    }
+
+    let mut crossings: HashMap<(i128, i128), Vec<usize>> = HashMap::new();
+    for (idx, a) in stretches.iter().enumerate() {
+        for b in &stretches[idx + 1..] {
+            if let Some(p) = crossing(a, b) {
+                let carriers = crossings.entry(p).or_default();
+                carriers.extend([a.carrier, b.carrier]);
+            }
+        }
+    }
+
+    // Points in a collinear overlap are counted once per carrier, so take
+    // back the repeats where overlaps on different carriers meet
+    let mut count = overlaps
+        .iter()
+        .flatten()
+        .map(|(start, end)| end - start + 1)
+        .sum::<i128>();
+    for (p, mut through) in crossings {
+        through.sort_unstable();
+        through.dedup();
+        let overlapping = through
+            .iter()
+            .filter(|c| contains(&overlaps[**c], position(p, carriers[**c].direction)))
+            .count() as i128;
+        count += if overlapping == 0 { 1 } else { 1 - overlapping };
+    }
+    count as usize
+}
@@ -0,0 +1,28 @@
+use num_bigint::BigUint;
+use std::fmt;
+
+/// A fish count that knows when it no longer fits
+pub trait Counter: Clone + Default + fmt::Debug + fmt::Display {
+    fn from_count(count: u64) -> Self;
+    /// `None` if the sum does not fit
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+}
+
+impl Counter for u64 {
+    fn from_count(count: u64) -> u64 {
+        count
+    }
+    fn checked_add(&self, other: &u64) -> Option<u64> {
+        u64::checked_add(*self, *other)
+    }
+}
+
+/// Never overflows, at the cost of an allocation per count
+impl Counter for BigUint {
+    fn from_count(count: u64) -> BigUint {
+        BigUint::from(count)
+    }
+    fn checked_add(&self, other: &BigUint) -> Option<BigUint> {
+        Some(self + other)
+    }
+}
@@ -0,0 +1,77 @@
+use super::{counter::Counter, FishColony};
+use anyhow::{anyhow as ah, Result};
+use std::io::Write;
+
+/// The colony as it was at the end of `day`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot<C> {
+    pub day: usize,
+    /// Fish counts, indexed by timer
+    pub fish: Vec<C>,
+    pub total: C,
+}
+
+/// Every day's snapshot starting from day 0, ending after the first overflow
+#[derive(Debug)]
+pub struct Snapshots<C> {
+    colony: Option<FishColony<C>>,
+    day: usize,
+}
+
+impl<C: Counter> Snapshots<C> {
+    pub fn new(colony: FishColony<C>) -> Snapshots<C> {
+        Snapshots {
+            colony: Some(colony),
+            day: 0,
+        }
+    }
+}
+
+impl<C: Counter> Iterator for Snapshots<C> {
+    type Item = Result<Snapshot<C>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let colony = self.colony.as_mut()?;
+        let stepped = self.day == 0 || colony.fish_day_step().is_some();
+        let total = colony.total().filter(|_| stepped);
+        let total = match total {
+            Some(total) => total,
+            None => {
+                self.colony = None;
+                return Some(Err(ah!("fish population overflowed on day {}", self.day)));
+            }
+        };
+        log::trace!("Day {}: {:?}", self.day, colony.fish);
+        let snapshot = Snapshot {
+            day: self.day,
+            fish: colony.fish.clone(),
+            total,
+        };
+        self.day += 1;
+        Some(Ok(snapshot))
+    }
+}
+
+/// Write `day,timer0,..,timerN,total` rows, one per snapshot
+pub fn write_csv<C: Counter>(
+    out: &mut dyn Write,
+    buckets: usize,
+    snapshots: impl Iterator<Item = Result<Snapshot<C>>>,
+) -> Result<()> {
+    let timers = (0..buckets).map(|t| format!("timer{}", t));
+    let header = std::iter::once("day".to_string())
+        .chain(timers)
+        .chain(std::iter::once("total".to_string()))
+        .collect::<Vec<_>>();
+    writeln!(out, "{}", header.join(","))?;
+    for snapshot in snapshots {
+        let snapshot = snapshot?;
+        let counts = snapshot.fish.iter().map(|c| c.to_string());
+        let row = std::iter::once(snapshot.day.to_string())
+            .chain(counts)
+            .chain(std::iter::once(snapshot.total.to_string()))
+            .collect::<Vec<_>>();
+        writeln!(out, "{}", row.join(","))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,147 @@
+use num_bigint::BigUint;
+
+/// The number system the colony is counted in
+pub trait Arithmetic {
+    type Value: Clone;
+    fn zero(&self) -> Self::Value;
+    fn count(&self, count: u64) -> Self::Value;
+    fn add(&self, a: &Self::Value, b: &Self::Value) -> Self::Value;
+    fn mul(&self, a: &Self::Value, b: &Self::Value) -> Self::Value;
+}
+
+/// Exact counts. Every day multiplies the population by roughly 1.09, so the
+/// answer grows by about one decimal digit per 27 days.
+#[derive(Debug, Clone, Copy)]
+pub struct Exact;
+
+impl Arithmetic for Exact {
+    type Value = BigUint;
+    fn zero(&self) -> BigUint {
+        BigUint::default()
+    }
+    fn count(&self, count: u64) -> BigUint {
+        BigUint::from(count)
+    }
+    fn add(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        a + b
+    }
+    fn mul(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        a * b
+    }
+}
+
+/// Counts modulo a fixed number
+#[derive(Debug, Clone, Copy)]
+pub struct Modular(pub u64);
+
+impl Arithmetic for Modular {
+    type Value = u64;
+    fn zero(&self) -> u64 {
+        0
+    }
+    fn count(&self, count: u64) -> u64 {
+        count % self.0
+    }
+    fn add(&self, a: &u64, b: &u64) -> u64 {
+        ((*a as u128 + *b as u128) % self.0 as u128) as u64
+    }
+    fn mul(&self, a: &u64, b: &u64) -> u64 {
+        ((*a as u128 * *b as u128) % self.0 as u128) as u64
+    }
+}
+
+/// A square matrix, stored row by row
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matrix<T> {
+    size: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> Matrix<T> {
+    pub fn identity<A: Arithmetic<Value = T>>(size: usize, arith: &A) -> Matrix<T> {
+        let mut m = Matrix {
+            size,
+            cells: vec![arith.zero(); size * size],
+        };
+        for i in 0..size {
+            m.cells[i * size + i] = arith.count(1);
+        }
+        m
+    }
+
+    /// Build a matrix from a table of small counts
+    pub fn from_counts<A: Arithmetic<Value = T>>(counts: &[Vec<u64>], arith: &A) -> Matrix<T> {
+        let size = counts.len();
+        let cells = counts
+            .iter()
+            .flat_map(|row| {
+                assert_eq!(row.len(), size, "matrix must be square");
+                row.iter().map(|c| arith.count(*c))
+            })
+            .collect();
+        Matrix { size, cells }
+    }
+
+    fn get(&self, row: usize, col: usize) -> &T {
+        &self.cells[row * self.size + col]
+    }
+
+    pub fn mul<A: Arithmetic<Value = T>>(&self, other: &Matrix<T>, arith: &A) -> Matrix<T> {
+        let n = self.size;
+        let mut cells = Vec::with_capacity(n * n);
+        for row in 0..n {
+            for col in 0..n {
+                let cell = (0..n).fold(arith.zero(), |acc, k| {
+                    arith.add(&acc, &arith.mul(self.get(row, k), other.get(k, col)))
+                });
+                cells.push(cell);
+            }
+        }
+        Matrix { size: n, cells }
+    }
+
+    /// Raise to the power `exp` by repeated squaring, in O(log exp) multiplications
+    pub fn pow<A: Arithmetic<Value = T>>(&self, mut exp: u64, arith: &A) -> Matrix<T> {
+        let mut result = Matrix::identity(self.size, arith);
+        let mut base = self.clone();
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = result.mul(&base, arith);
+            }
+            exp >>= 1;
+            if exp > 0 {
+                base = base.mul(&base, arith);
+            }
+        }
+        result
+    }
+
+    pub fn apply<A: Arithmetic<Value = T>>(&self, vector: &[T], arith: &A) -> Vec<T> {
+        assert_eq!(vector.len(), self.size, "vector does not match matrix");
+        (0..self.size)
+            .map(|row| {
+                vector
+                    .iter()
+                    .enumerate()
+                    .fold(arith.zero(), |acc, (col, v)| {
+                        arith.add(&acc, &arith.mul(self.get(row, col), v))
+                    })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fibonacci_by_squaring() {
+        let fib = vec![vec![1, 1], vec![1, 0]];
+        let m = Matrix::from_counts(&fib, &Exact).pow(100, &Exact);
+        assert_eq!(m.get(0, 1).to_string(), "354224848179261915075");
+        let modular = Modular(1_000_000_007);
+        let m = Matrix::from_counts(&fib, &modular).pow(100, &modular);
+        assert_eq!(*m.get(0, 1), 687995182);
+    }
+}
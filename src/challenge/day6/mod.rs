@@ -1,26 +1,134 @@
-use anyhow::{Context, Result};
+use super::{
+    input, parse_arg,
+    solution::{Day, Solution},
+};
+use anyhow::{anyhow as ah, Context, Result};
+use counter::Counter;
+use history::Snapshots;
+use matrix::{Arithmetic, Exact, Matrix, Modular};
+use num_bigint::BigUint;
+use std::io;
+
+mod counter;
+mod history;
+mod matrix;
 
 const FISH_BREED_RATE: usize = 6;
 const FISH_INCUBATION_RATE: usize = 8;
-const FISH_LEN: usize = FISH_INCUBATION_RATE + 1;
 const PART_1_FISH_TIME: usize = 80;
 const PART_2_FISH_TIME: usize = 256;
+/// Longest timer a fish can have, which keeps the transition matrix small
+const MAX_FISH_TIMER: usize = 1 << 10;
+
+pub struct Day6;
+
+pub const SOLUTION: Solution = Solution::new::<Day6>();
+
+impl Day for Day6 {
+    const DAY: u32 = 6;
+    const TITLE: &'static str = "Lanternfish";
+    const EXAMPLE: &'static str = include_str!("../../../input/day6_ex");
+
+    type Input = FishColony;
+    type Answer1 = u64;
+    type Answer2 = u64;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        parse(input)
+    }
+    fn part1(colony: &Self::Input) -> Result<u64> {
+        part1(colony)
+    }
+    fn part2(colony: &Self::Input) -> Result<u64> {
+        part2(colony)
+    }
+}
 
-pub fn part1(input: &str) -> Result<String> {
-    let fish = parse(input)?;
-    let mut colony = FishColony::new(&fish);
-    colony.fish_days(PART_1_FISH_TIME);
-    Ok(format!("{:?}", colony.total()))
+pub fn part1(colony: &FishColony) -> Result<u64> {
+    colony.total_after_steps(PART_1_FISH_TIME)
 }
 
-pub fn part2(input: &str) -> Result<String> {
-    let fish = parse(input)?;
-    let mut colony = FishColony::new(&fish);
-    colony.fish_days(PART_2_FISH_TIME);
-    Ok(format!("{:?}", colony.total()))
+pub fn part2(colony: &FishColony) -> Result<u64> {
+    colony.total_after_steps(PART_2_FISH_TIME)
 }
 
-fn parse(input: &str) -> Result<Vec<i64>> {
+/// Count the fish after an arbitrary number of days, for the `fish` subcommand
+pub fn simulate(args: &clap::ArgMatches) -> Result<()> {
+    let days = parse_arg(args, "days", PART_2_FISH_TIME as u64)?;
+    let cycle = LifeCycle {
+        breed: parse_arg(args, "breed", FISH_BREED_RATE)?,
+        incubate: parse_arg(args, "incubate", FISH_INCUBATION_RATE)?,
+        maturity: parse_arg(args, "maturity", 0)?,
+    };
+    let data = input::load(
+        args.value_of("input"),
+        &SOLUTION,
+        args.is_present("example"),
+    )?
+    .data;
+    let timers = parse_timers(&data)?;
+    if args.is_present("csv") {
+        let days = days as usize;
+        let out = &mut io::stdout().lock();
+        if args.is_present("big") {
+            let colony = FishColony::<BigUint>::new(&timers, cycle)?;
+            history::write_csv(
+                out,
+                colony.fish.len(),
+                colony.snapshots().take(days.saturating_add(1)),
+            )?;
+        } else {
+            let colony = FishColony::<u64>::new(&timers, cycle)?;
+            history::write_csv(
+                out,
+                colony.fish.len(),
+                colony.snapshots().take(days.saturating_add(1)),
+            )?;
+        }
+        return Ok(());
+    }
+    if args.is_present("step") {
+        let days = days as usize;
+        if args.is_present("big") {
+            println!(
+                "{}",
+                FishColony::<BigUint>::new(&timers, cycle)?.total_after_steps(days)?
+            );
+        } else {
+            println!(
+                "{}",
+                FishColony::<u64>::new(&timers, cycle)?.total_after_steps(days)?
+            );
+        }
+        return Ok(());
+    }
+    let colony = FishColony::<u64>::new(&timers, cycle)?;
+    if let Some(n) = args.value_of("exceeds") {
+        let threshold = n
+            .parse::<BigUint>()
+            .with_context(|| format!("could not parse threshold: {:?}", n))?;
+        match colony.first_day_exceeding(&threshold) {
+            Some(day) => println!("{}", day),
+            None => anyhow::bail!("the population never exceeds {}", threshold),
+        }
+        return Ok(());
+    }
+    match args.value_of("modulus") {
+        Some(m) => {
+            let modulus = m
+                .parse::<u64>()
+                .with_context(|| format!("could not parse modulus: {:?}", m))?;
+            if modulus == 0 {
+                anyhow::bail!("modulus must be positive");
+            }
+            println!("{}", colony.total_after(days, &Modular(modulus)));
+        }
+        None => println!("{}", colony.total_after(days, &Exact)),
+    }
+    Ok(())
+}
+
+fn parse_timers(input: &str) -> Result<Vec<i64>> {
     input
         .trim()
         .split(',')
@@ -31,64 +139,373 @@ fn parse(input: &str) -> Result<Vec<i64>> {
         .collect()
 }
 
-#[derive(Debug, Default)]
-struct FishColony {
-    fish: [usize; FISH_LEN],
+fn parse(input: &str) -> Result<FishColony> {
+    FishColony::new(&parse_timers(input)?, LifeCycle::default())
 }
 
-impl FishColony {
-    fn new(starting_fish: &[i64]) -> FishColony {
-        let mut colony = FishColony::default();
-        for f in starting_fish {
-            colony.fish[*f as usize] += 1;
+/// How a species of fish breeds
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LifeCycle {
+    /// Timer an adult resets to after spawning
+    pub breed: usize,
+    /// Timer a newborn fish starts with
+    pub incubate: usize,
+    /// Extra days before the starting fish begin counting down
+    pub maturity: usize,
+}
+
+impl Default for LifeCycle {
+    fn default() -> Self {
+        LifeCycle {
+            breed: FISH_BREED_RATE,
+            incubate: FISH_INCUBATION_RATE,
+            maturity: 0,
         }
-        colony
     }
-    fn fish_day_step(&mut self) {
-        let mut new_fish = [0usize; FISH_LEN];
-        for (idx, c) in self.fish.into_iter().enumerate() {
+}
+
+/// Fish counted by days until they next breed. Counts are checked `u64` by
+/// default; use `FishColony<BigUint>` for populations that do not fit.
+#[derive(Debug, Clone)]
+pub struct FishColony<C = u64> {
+    cycle: LifeCycle,
+    fish: Vec<C>,
+}
+
+impl<C: Counter> FishColony<C> {
+    fn new(starting_fish: &[i64], cycle: LifeCycle) -> Result<FishColony<C>> {
+        for (name, timer) in [
+            ("breed", cycle.breed),
+            ("incubate", cycle.incubate),
+            ("maturity", cycle.maturity),
+        ] {
+            if timer > MAX_FISH_TIMER {
+                anyhow::bail!(
+                    "{} timer {} is too long, the limit is {}",
+                    name,
+                    timer,
+                    MAX_FISH_TIMER
+                );
+            }
+        }
+        let timers = starting_fish
+            .iter()
+            .map(|f| {
+                let timer = usize::try_from(*f)
+                    .map_err(|_| ah!("fish timer can not be negative: {}", f))?;
+                timer
+                    .checked_add(cycle.maturity)
+                    .filter(|t| *t <= MAX_FISH_TIMER)
+                    .ok_or_else(|| {
+                        ah!(
+                            "fish timer {} is too long after maturity, the limit is {}",
+                            f,
+                            MAX_FISH_TIMER
+                        )
+                    })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let len = timers
+            .iter()
+            .copied()
+            .chain([cycle.breed, cycle.incubate])
+            .max()
+            .unwrap_or_default()
+            + 1;
+        let mut counts = vec![0u64; len];
+        for t in timers {
+            counts[t] += 1;
+        }
+        Ok(FishColony {
+            cycle,
+            fish: counts.into_iter().map(C::from_count).collect(),
+        })
+    }
+    /// Advance a day, or `None` if a count overflowed
+    fn fish_day_step(&mut self) -> Option<()> {
+        let mut new_fish = vec![C::default(); self.fish.len()];
+        for (idx, c) in self.fish.iter().enumerate() {
             if idx == 0 {
-                new_fish[FISH_BREED_RATE] = c;
-                new_fish[FISH_INCUBATION_RATE] = c;
+                let breed = self.cycle.breed;
+                let incubate = self.cycle.incubate;
+                new_fish[breed] = new_fish[breed].checked_add(c)?;
+                new_fish[incubate] = new_fish[incubate].checked_add(c)?;
             } else {
-                new_fish[idx - 1] += c;
+                new_fish[idx - 1] = new_fish[idx - 1].checked_add(c)?;
             }
         }
-        self.fish = new_fish
+        self.fish = new_fish;
+        Some(())
+    }
+    fn total(&self) -> Option<C> {
+        self.fish
+            .iter()
+            .try_fold(C::default(), |total, f| total.checked_add(f))
+    }
+    /// Each day's state, starting with the colony as it is now
+    pub fn snapshots(&self) -> Snapshots<C> {
+        Snapshots::new(self.clone())
     }
-    fn fish_days(&mut self, days: usize) {
-        log::trace!("Initial State: {:?}", self);
-        for d in 0..days {
-            self.fish_day_step();
-            log::trace!("Day {}: {:?}", d, self);
+    /// Step through `days` one at a time and count the fish at the end
+    fn total_after_steps(&self, days: usize) -> Result<C> {
+        let mut total = C::default();
+        for snapshot in self.snapshots().take(days.saturating_add(1)) {
+            total = snapshot?.total;
+        }
+        Ok(total)
+    }
+}
+
+impl FishColony {
+    /// One day of breeding as a linear map, `next[i] = sum(m[i][j] * fish[j])`
+    fn transition<A: Arithmetic>(&self, arith: &A) -> Matrix<A::Value> {
+        let len = self.fish.len();
+        let mut counts = vec![vec![0; len]; len];
+        for timer in 1..len {
+            counts[timer - 1][timer] = 1;
         }
+        counts[self.cycle.breed][0] += 1;
+        counts[self.cycle.incubate][0] += 1;
+        Matrix::from_counts(&counts, arith)
+    }
+
+    fn counts<A: Arithmetic>(&self, arith: &A) -> Vec<A::Value> {
+        self.fish.iter().map(|f| arith.count(*f)).collect()
+    }
+
+    /// The population after `days`, computed in O(log days) matrix multiplications
+    fn total_after<A: Arithmetic>(&self, days: u64, arith: &A) -> A::Value {
+        let fish = self.counts(arith);
+        let fish = self.transition(arith).pow(days, arith).apply(&fish, arith);
+        sum(&fish, arith)
     }
-    fn total(&self) -> usize {
-        self.fish.iter().sum()
+
+    /// The first day the population is larger than `threshold`, or `None` if
+    /// it never grows. Since the population never shrinks, this searches over
+    /// the powers `M^(2^k)` of the transition matrix instead of stepping
+    /// through every day.
+    pub fn first_day_exceeding(&self, threshold: &BigUint) -> Option<u64> {
+        let start = self.counts(&Exact);
+        let total = sum(&start, &Exact);
+        if total > *threshold {
+            return Some(0);
+        }
+        if total == BigUint::default() {
+            return None;
+        }
+
+        // M^(2^k), until jumping that far from the start is enough
+        let mut powers = vec![self.transition(&Exact)];
+        loop {
+            let last = powers.last().unwrap();
+            if sum(&last.apply(&start, &Exact), &Exact) > *threshold {
+                break;
+            }
+            if powers.len() == u64::BITS as usize - 1 {
+                return None;
+            }
+            powers.push(last.mul(last, &Exact));
+        }
+
+        // the last day at or below the threshold, one power of two at a time
+        let mut fish = start;
+        let mut day = 0u64;
+        for (k, power) in powers.iter().enumerate().rev() {
+            let next = power.apply(&fish, &Exact);
+            if sum(&next, &Exact) <= *threshold {
+                fish = next;
+                day += 1 << k;
+            }
+        }
+        Some(day + 1)
     }
 }
 
+fn sum<A: Arithmetic>(fish: &[A::Value], arith: &A) -> A::Value {
+    fish.iter()
+        .fold(arith.zero(), |total, f| arith.add(&total, f))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::challenge::answers::expected;
     const INPUT: &str = include_str!("../../../input/day6");
     const EX: &str = include_str!("../../../input/day6_ex");
 
     #[test]
     fn verify_p1() {
-        assert_eq!(part1(INPUT).unwrap().as_str(), "385391")
+        assert_eq!(
+            part1(&parse(INPUT).unwrap()).unwrap().to_string(),
+            expected(6, "day6", 1)
+        )
     }
     #[test]
     fn verify_p2() {
-        assert_eq!(part2(INPUT).unwrap().as_str(), "1728611055389")
+        assert_eq!(
+            part2(&parse(INPUT).unwrap()).unwrap().to_string(),
+            expected(6, "day6", 2)
+        )
     }
 
     #[test]
     fn check_example_p1() {
-        assert_eq!(part1(EX).unwrap().as_str(), "5934")
+        assert_eq!(
+            part1(&parse(EX).unwrap()).unwrap().to_string(),
+            expected(6, "day6_ex", 1)
+        )
     }
     #[test]
     fn check_example_p2() {
-        assert_eq!(part2(EX).unwrap().as_str(), "26984457539")
+        assert_eq!(
+            part2(&parse(EX).unwrap()).unwrap().to_string(),
+            expected(6, "day6_ex", 2)
+        )
+    }
+    #[test]
+    fn matrix_matches_stepping() {
+        let colony = parse(INPUT).unwrap();
+        for days in [0, 1, 18, 80, 256] {
+            assert_eq!(
+                colony.total_after(days as u64, &Exact).to_string(),
+                colony.total_after_steps(days).unwrap().to_string(),
+                "day {}",
+                days
+            );
+        }
+    }
+    #[test]
+    fn matrix_modular() {
+        let colony = parse(EX).unwrap();
+        let exact = colony.total_after(1000, &Exact);
+        let modular = colony.total_after(1000, &Modular(1_000_000_007));
+        assert_eq!((exact % 1_000_000_007u64).to_string(), modular.to_string());
+        // far too many days to simulate, or to count exactly
+        colony.total_after(1_000_000_000_000_000_000, &Modular(1_000_000_007));
+    }
+    #[test]
+    fn overflow_names_day() {
+        let timers = parse_timers(INPUT).unwrap();
+        let cycle = LifeCycle::default();
+        let e = FishColony::<u64>::new(&timers, cycle)
+            .unwrap()
+            .total_after_steps(500)
+            .unwrap_err();
+        assert!(e
+            .to_string()
+            .starts_with("fish population overflowed on day "));
+        // stepping for as long as possible stops at the overflow too
+        assert!(FishColony::<u64>::new(&timers, cycle)
+            .unwrap()
+            .total_after_steps(usize::MAX)
+            .is_err());
+        let big = FishColony::<BigUint>::new(&timers, cycle)
+            .unwrap()
+            .total_after_steps(500)
+            .unwrap();
+        let colony = FishColony::<u64>::new(&timers, cycle).unwrap();
+        assert_eq!(big, colony.total_after(500, &Exact));
+    }
+    #[test]
+    fn custom_life_cycle() {
+        let timers = parse_timers(EX).unwrap();
+        // breeding at the same rate as incubation doubles the colony every cycle
+        let cycle = LifeCycle {
+            breed: 2,
+            incubate: 2,
+            maturity: 0,
+        };
+        let colony = FishColony::<u64>::new(&[0], cycle).unwrap();
+        assert_eq!(colony.total_after_steps(1).unwrap(), 2);
+        assert_eq!(colony.total_after_steps(4).unwrap(), 4);
+        assert_eq!(colony.total_after_steps(7).unwrap(), 8);
+
+        let cycle = LifeCycle {
+            breed: 4,
+            incubate: 11,
+            maturity: 3,
+        };
+        let colony = FishColony::<u64>::new(&timers, cycle).unwrap();
+        for days in [0, 3, 50, 200] {
+            assert_eq!(
+                colony.total_after(days as u64, &Exact).to_string(),
+                colony.total_after_steps(days).unwrap().to_string(),
+                "day {}",
+                days
+            );
+        }
+        // nobody breeds until their delayed timers run out
+        assert_eq!(colony.total_after_steps(4).unwrap(), 5);
+
+        for cycle in [
+            LifeCycle {
+                breed: usize::MAX,
+                ..LifeCycle::default()
+            },
+            LifeCycle {
+                incubate: 4_000_000_000_000,
+                ..LifeCycle::default()
+            },
+            LifeCycle {
+                maturity: usize::MAX,
+                ..LifeCycle::default()
+            },
+            LifeCycle {
+                maturity: MAX_FISH_TIMER,
+                ..LifeCycle::default()
+            },
+        ] {
+            assert!(FishColony::<u64>::new(&timers, cycle).is_err());
+        }
+        assert!(FishColony::<u64>::new(&[i64::MAX], LifeCycle::default()).is_err());
+    }
+    #[test]
+    fn snapshots_match_totals() {
+        let colony = parse(EX).unwrap();
+        let snapshots = colony
+            .snapshots()
+            .take(81)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(snapshots[0].fish, vec![0, 1, 1, 2, 1, 0, 0, 0, 0]);
+        assert_eq!(snapshots[18].total, 26);
+        assert_eq!(snapshots[80].total, 5934);
+        assert!(snapshots.iter().enumerate().all(|(d, s)| s.day == d));
+    }
+    #[test]
+    fn csv_export() {
+        let colony = parse(EX).unwrap();
+        let mut out = Vec::new();
+        history::write_csv(&mut out, 9, colony.snapshots().take(2)).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "day,timer0,timer1,timer2,timer3,timer4,timer5,timer6,timer7,timer8,total\n\
+             0,0,1,1,2,1,0,0,0,0,5\n\
+             1,1,1,2,1,0,0,0,0,0,5\n"
+        );
+    }
+    #[test]
+    fn first_day_exceeding() {
+        let colony = parse(EX).unwrap();
+        let snapshots = colony
+            .snapshots()
+            .take(300)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        for threshold in [0u64, 4, 5, 25, 26, 5933, 5934, 26984457539] {
+            let expected = snapshots.iter().find(|s| s.total > threshold).unwrap().day;
+            assert_eq!(
+                colony.first_day_exceeding(&BigUint::from(threshold)),
+                Some(expected as u64),
+                "threshold {}",
+                threshold
+            );
+        }
+        let empty = FishColony::<u64>::new(&[], LifeCycle::default()).unwrap();
+        assert_eq!(empty.first_day_exceeding(&BigUint::from(1u64)), None);
+    }
+    #[test]
+    fn negative_timer() {
+        assert!(parse("3,-1").is_err());
     }
 }
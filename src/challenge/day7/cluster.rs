@@ -0,0 +1,259 @@
+use super::{align, cost::CostFunction, narrow, widen};
+use anyhow::Result;
+use std::collections::HashMap;
+
+/// Where each group of crabs lines up, and what it costs them
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clustering {
+    /// Target positions, in ascending order
+    pub targets: Vec<i64>,
+    pub fuel: i64,
+    /// Index into `targets` for each crab, in input order
+    pub assignment: Vec<usize>,
+    /// Fuel spent by each crab, in input order
+    pub costs: Vec<i64>,
+}
+
+impl std::fmt::Display for Clustering {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} crabs align at {:?} using {} fuel",
+            self.costs.len(),
+            self.targets,
+            self.fuel
+        )
+    }
+}
+
+/// A cost function for a run of sorted crabs, reporting their input indices
+/// to `inner` so per-crab costs still line up
+struct Reindexed<'a> {
+    inner: &'a dyn CostFunction,
+    ids: &'a [usize],
+}
+
+impl CostFunction for Reindexed<'_> {
+    fn cost(&self, crab: usize, from: i64, to: i64) -> i64 {
+        self.inner.cost(self.ids[crab], from, to)
+    }
+    fn is_convex(&self) -> bool {
+        self.inner.is_convex()
+    }
+    fn shortcut(&self, crabs: &[i64]) -> Option<Vec<i64>> {
+        self.inner.shortcut(crabs)
+    }
+}
+
+/// Most entries in the prefix sum table used for costs that are not convex
+const MAX_PREFIX_CELLS: u128 = 1 << 22;
+
+/// Cheapest single-target fuel for each run of sorted crabs, computed on demand
+struct Runs<'a> {
+    positions: Vec<i64>,
+    ids: Vec<usize>,
+    cost: &'a dyn CostFunction,
+    /// `prefix[i][t]` is the fuel for the first `i` sorted crabs to reach the
+    /// `t`th position from the leftmost crab, for costs without a fast search
+    prefix: Option<Vec<Vec<i128>>>,
+    fuel: HashMap<(usize, usize), i64>,
+}
+
+impl<'a> Runs<'a> {
+    fn new(crabs: &[i64], cost: &'a dyn CostFunction) -> Result<Runs<'a>> {
+        let mut ids = (0..crabs.len()).collect::<Vec<_>>();
+        ids.sort_by_key(|id| crabs[*id]);
+        let positions = ids.iter().map(|id| crabs[*id]).collect::<Vec<_>>();
+        let prefix = if cost.shortcut(&positions).is_none() && !cost.is_convex() {
+            Some(prefix_sums(&positions, &ids, cost)?)
+        } else {
+            None
+        };
+        Ok(Runs {
+            positions,
+            ids,
+            cost,
+            prefix,
+            fuel: HashMap::new(),
+        })
+    }
+
+    fn align(&self, start: usize, end: usize) -> Result<super::Alignment> {
+        let cost = Reindexed {
+            inner: self.cost,
+            ids: &self.ids[start..end],
+        };
+        align(&self.positions[start..end], &cost)
+    }
+
+    /// Fuel for sorted crabs `start..end` to share one target, `i64::MAX` if
+    /// it overflows wherever they meet
+    fn fuel(&mut self, start: usize, end: usize) -> Result<i64> {
+        if let Some(fuel) = self.fuel.get(&(start, end)) {
+            return Ok(*fuel);
+        }
+        let fuel = match &self.prefix {
+            // every target between the outermost crabs of the run
+            Some(prefix) => {
+                let origin = self.positions[0];
+                let lo = self.positions[start].abs_diff(origin) as usize;
+                let hi = self.positions[end - 1].abs_diff(origin) as usize;
+                let fuel = (lo..=hi)
+                    .map(|t| prefix[end][t] - prefix[start][t])
+                    .min()
+                    .unwrap_or(i128::MAX);
+                narrow(fuel)
+            }
+            None => self.align(start, end).map_or(i64::MAX, |a| a.fuel),
+        };
+        self.fuel.insert((start, end), fuel);
+        Ok(fuel)
+    }
+}
+
+/// Running totals of every sorted crab's fuel to every position between the
+/// outermost crabs, so any run can be costed without calling `cost` again
+fn prefix_sums(
+    positions: &[i64],
+    ids: &[usize],
+    cost: &dyn CostFunction,
+) -> Result<Vec<Vec<i128>>> {
+    let (first, last) = (positions[0], positions[positions.len() - 1]);
+    let width = u128::from(last.abs_diff(first)) + 1;
+    let cells = width * (positions.len() as u128 + 1);
+    if cells > MAX_PREFIX_CELLS {
+        anyhow::bail!(
+            "too many crabs and positions to cluster with a cost that is not convex: \
+             {} crabs over {} positions, the limit is {} crab positions",
+            positions.len(),
+            width,
+            MAX_PREFIX_CELLS
+        );
+    }
+    let mut prefix = vec![vec![0; width as usize]];
+    for (idx, (id, from)) in ids.iter().zip(positions).enumerate() {
+        let row = (first..=last)
+            .zip(&prefix[idx])
+            .map(|(to, before)| before + widen(cost.cost(*id, *from, to)))
+            .collect();
+        prefix.push(row);
+    }
+    Ok(prefix)
+}
+
+/// Fill `next[lo..=hi]` with the best split for each end, knowing the best
+/// split only moves right as the end does
+#[allow(clippy::too_many_arguments)]
+fn divide(
+    runs: &mut Runs,
+    prev: &[i64],
+    next: &mut [i64],
+    split: &mut [usize],
+    lo: usize,
+    hi: usize,
+    opt_lo: usize,
+    opt_hi: usize,
+) -> Result<()> {
+    if lo > hi {
+        return Ok(());
+    }
+    let mid = (lo + hi) / 2;
+    let mut best = (i64::MAX, opt_lo);
+    let last = opt_hi.min(mid - 1);
+    for (p, before) in prev.iter().enumerate().take(last + 1).skip(opt_lo) {
+        if *before == i64::MAX {
+            continue;
+        }
+        let fuel = before.saturating_add(runs.fuel(p, mid)?);
+        if fuel < best.0 {
+            best = (fuel, p);
+        }
+    }
+    next[mid] = best.0;
+    split[mid] = best.1;
+    if mid > lo {
+        divide(runs, prev, next, split, lo, mid - 1, opt_lo, best.1)?;
+    }
+    divide(runs, prev, next, split, mid + 1, hi, best.1, opt_hi)
+}
+
+/// Choose `k` targets so the crabs, each going to its cheapest one, spend as
+/// little fuel as possible.
+///
+/// As long as a crab's cost never drops as it moves further away, the crabs
+/// sharing a target are a contiguous run of the sorted positions, so this is
+/// dynamic programming over where each run starts. Convex costs use divide
+/// and conquer over the split points, evaluating O(k n log n) runs. Anything
+/// else tries every split, O(k n^2) runs, each read off prefix sums of every
+/// crab's fuel to every position, so the crabs times the width of their range
+/// is limited.
+pub fn cluster(crabs: &[i64], k: usize, cost: &dyn CostFunction) -> Result<Clustering> {
+    if k == 0 {
+        anyhow::bail!("need at least one target");
+    }
+    if crabs.is_empty() {
+        anyhow::bail!("no crabs to align");
+    }
+    let n = crabs.len();
+    let k = k.min(n);
+    let mut runs = Runs::new(crabs, cost)?;
+
+    // best[i] is the least fuel for the first i sorted crabs with j targets
+    let mut best = vec![i64::MAX; n + 1];
+    for (end, fuel) in best.iter_mut().enumerate().skip(1) {
+        *fuel = runs.fuel(0, end)?;
+    }
+    let mut splits = Vec::new();
+    for j in 2..=k {
+        let mut next = vec![i64::MAX; n + 1];
+        let mut split = vec![0; n + 1];
+        if cost.is_convex() {
+            divide(&mut runs, &best, &mut next, &mut split, j, n, j - 1, n - 1)?;
+        } else {
+            for end in j..=n {
+                for (p, before) in best.iter().enumerate().take(end).skip(j - 1) {
+                    let fuel = before.saturating_add(runs.fuel(p, end)?);
+                    if fuel < next[end] {
+                        next[end] = fuel;
+                        split[end] = p;
+                    }
+                }
+            }
+        }
+        best = next;
+        splits.push(split);
+    }
+
+    if best[n] == i64::MAX {
+        anyhow::bail!("fuel overflows for every choice of {} targets", k);
+    }
+
+    let mut bounds = Vec::with_capacity(k);
+    let mut end = n;
+    for split in splits.iter().rev() {
+        bounds.push((split[end], end));
+        end = split[end];
+    }
+    bounds.push((0, end));
+    bounds.reverse();
+
+    let mut clustering = Clustering {
+        targets: Vec::with_capacity(k),
+        fuel: 0,
+        assignment: vec![0; n],
+        costs: vec![0; n],
+    };
+    for (target, (start, end)) in bounds.into_iter().enumerate() {
+        let alignment = runs.align(start, end)?;
+        for (id, cost) in runs.ids[start..end].iter().zip(alignment.costs) {
+            clustering.assignment[*id] = target;
+            clustering.costs[*id] = cost;
+        }
+        clustering.targets.push(alignment.position);
+        clustering.fuel = clustering
+            .fuel
+            .checked_add(alignment.fuel)
+            .ok_or_else(|| anyhow::anyhow!("fuel overflows for {} targets", k))?;
+    }
+    Ok(clustering)
+}
@@ -0,0 +1,156 @@
+use super::{arithmetic_progression, mean_neighbourhood, median};
+use anyhow::{Context, Result};
+
+/// The fuel a crab spends to move
+pub trait CostFunction {
+    /// Fuel for crab number `crab` to move from `from` to `to`, saturating at
+    /// `i64::MAX` rather than overflowing
+    fn cost(&self, crab: usize, from: i64, to: i64) -> i64;
+
+    /// Whether the cost grows convexly with distance, which makes the total
+    /// convex in the target and lets it be found by ternary search
+    fn is_convex(&self) -> bool {
+        false
+    }
+
+    /// A few targets known to contain the optimum, if there is a closed form
+    fn shortcut(&self, _crabs: &[i64]) -> Option<Vec<i64>> {
+        None
+    }
+}
+
+/// Steps between two positions, saturating like the costs built from it
+fn distance(from: i64, to: i64) -> i64 {
+    i64::try_from(from.abs_diff(to)).unwrap_or(i64::MAX)
+}
+
+/// One fuel per step
+#[derive(Debug, Clone, Copy)]
+pub struct Linear;
+
+impl CostFunction for Linear {
+    fn cost(&self, _crab: usize, from: i64, to: i64) -> i64 {
+        distance(from, to)
+    }
+    fn is_convex(&self) -> bool {
+        true
+    }
+    /// The sum of distances is smallest at the median
+    fn shortcut(&self, crabs: &[i64]) -> Option<Vec<i64>> {
+        median(crabs).ok().map(|m| vec![m])
+    }
+}
+
+/// Each step costs one more than the last
+#[derive(Debug, Clone, Copy)]
+pub struct Step;
+
+impl CostFunction for Step {
+    fn cost(&self, _crab: usize, from: i64, to: i64) -> i64 {
+        arithmetic_progression(distance(from, to))
+    }
+    fn is_convex(&self) -> bool {
+        true
+    }
+    /// The optimum for triangular costs is within 1 of the mean
+    fn shortcut(&self, crabs: &[i64]) -> Option<Vec<i64>> {
+        mean_neighbourhood(crabs).ok().map(|n| n.collect())
+    }
+}
+
+/// The square of the distance
+#[derive(Debug, Clone, Copy)]
+pub struct Quadratic;
+
+impl CostFunction for Quadratic {
+    fn cost(&self, _crab: usize, from: i64, to: i64) -> i64 {
+        distance(from, to).saturating_pow(2)
+    }
+    fn is_convex(&self) -> bool {
+        true
+    }
+}
+
+/// `c0 + c1*d + c2*d^2 + ...` for a distance `d`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polynomial {
+    pub coefficients: Vec<i64>,
+}
+
+impl std::str::FromStr for Polynomial {
+    type Err = anyhow::Error;
+
+    /// Comma separated coefficients, lowest power first
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let coefficients = s
+            .split(',')
+            .map(|c| {
+                c.trim()
+                    .parse::<i64>()
+                    .with_context(|| format!("could not parse number: {:?}", c))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Polynomial { coefficients })
+    }
+}
+
+impl CostFunction for Polynomial {
+    fn cost(&self, _crab: usize, from: i64, to: i64) -> i64 {
+        let distance = i128::from(distance(from, to));
+        let saturate = |value: i128| if value > 0 { i64::MAX } else { i64::MIN };
+        let mut value = 0i128;
+        for c in self.coefficients.iter().rev() {
+            match value
+                .checked_mul(distance)
+                .and_then(|v| v.checked_add(i128::from(*c)))
+            {
+                Some(v) => value = v,
+                // past i128 the lower terms can no longer bring it back
+                None => return saturate(value),
+            }
+        }
+        i64::try_from(value).unwrap_or_else(|_| saturate(value))
+    }
+    /// Every non-constant term is convex in the distance if its coefficient is
+    fn is_convex(&self) -> bool {
+        self.coefficients.iter().skip(1).all(|c| *c >= 0)
+    }
+}
+
+/// No crab spends more than `cap`
+pub struct Capped {
+    pub inner: Box<dyn CostFunction>,
+    pub cap: i64,
+}
+
+impl CostFunction for Capped {
+    fn cost(&self, crab: usize, from: i64, to: i64) -> i64 {
+        self.inner.cost(crab, from, to).min(self.cap)
+    }
+}
+
+/// Each crab's cost scaled by its own weight, crabs without one weigh 1
+pub struct Weighted {
+    pub inner: Box<dyn CostFunction>,
+    pub weights: Vec<i64>,
+}
+
+impl CostFunction for Weighted {
+    fn cost(&self, crab: usize, from: i64, to: i64) -> i64 {
+        let weight = self.weights.get(crab).copied().unwrap_or(1);
+        weight.saturating_mul(self.inner.cost(crab, from, to))
+    }
+    fn is_convex(&self) -> bool {
+        self.inner.is_convex() && self.weights.iter().all(|w| *w >= 0)
+    }
+}
+
+/// Look up a built-in cost function by name
+pub fn named(name: &str) -> Result<Box<dyn CostFunction>> {
+    Ok(match name {
+        "linear" => Box::new(Linear),
+        "step" => Box::new(Step),
+        "quadratic" => Box::new(Quadratic),
+        _ => anyhow::bail!("unknown cost function: {:?}", name),
+    })
+}
@@ -1,28 +1,221 @@
+use super::{
+    input, parse_arg,
+    solution::{Day, Solution},
+};
 use anyhow::{Context, Result};
+use cost::{Capped, CostFunction, Linear, Polynomial, Step, Weighted};
 
-pub fn part1(input: &str) -> Result<String> {
-    let fuel = find_min_fuel_transfer(input, linear_error)?;
-    Ok(format!("{:?}", fuel))
+mod cluster;
+mod cost;
+
+pub struct Day7;
+
+pub const SOLUTION: Solution = Solution::new::<Day7>();
+
+impl Day for Day7 {
+    const DAY: u32 = 7;
+    const TITLE: &'static str = "The Treachery of Whales";
+    const EXAMPLE: &'static str = include_str!("../../../input/day7_ex");
+
+    type Input = Vec<i64>;
+    type Answer1 = i64;
+    type Answer2 = i64;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        parse(input)
+    }
+    fn part1(crabs: &Self::Input) -> Result<i64> {
+        part1(crabs)
+    }
+    fn part2(crabs: &Self::Input) -> Result<i64> {
+        part2(crabs)
+    }
 }
 
-pub fn part2(input: &str) -> Result<String> {
-    let fuel = find_min_fuel_transfer(input, step_error)?;
-    Ok(format!("{:?}", fuel))
+pub fn part1(crabs: &[i64]) -> Result<i64> {
+    let alignment = align(crabs, &Linear)?;
+    alignment.log();
+    Ok(alignment.fuel)
 }
 
-fn find_min_fuel_transfer<F>(input: &str, err_func: F) -> Result<i64>
-where
-    F: Fn(i64, i64) -> i64,
-{
-    let crabs = parse(input)?;
-    let (min, max) = minmax(&crabs)?;
-    let (target, fuel) = (min..max)
-        .enumerate()
-        .map(|(idx, t)| (idx, total_error(&crabs, t, &err_func)))
+pub fn part2(crabs: &[i64]) -> Result<i64> {
+    let alignment = align(crabs, &Step)?;
+    alignment.log();
+    Ok(alignment.fuel)
+}
+
+/// Align the crabs under any cost function for the `crabs` subcommand
+pub fn simulate(args: &clap::ArgMatches) -> Result<()> {
+    let data = input::load(
+        args.value_of("input"),
+        &SOLUTION,
+        args.is_present("example"),
+    )?
+    .data;
+    let crabs = parse(&data)?;
+
+    let mut cost = match args.value_of("poly") {
+        Some(p) => Box::new(p.parse::<Polynomial>()?),
+        None => cost::named(args.value_of("cost").unwrap_or("linear"))?,
+    };
+    if let Some(w) = args.value_of("weights") {
+        let weights = parse(w)?;
+        if weights.len() != crabs.len() {
+            anyhow::bail!("got {} weights for {} crabs", weights.len(), crabs.len());
+        }
+        cost = Box::new(Weighted {
+            inner: cost,
+            weights,
+        });
+    }
+    if let Some(c) = args.value_of("cap") {
+        let cap = c
+            .parse::<i64>()
+            .with_context(|| format!("could not parse cap: {:?}", c))?;
+        cost = Box::new(Capped { inner: cost, cap });
+    }
+
+    let targets = parse_arg(args, "targets", 1usize)?;
+    if targets == 1 {
+        let alignment = align(&crabs, cost.as_ref())?;
+        alignment.log();
+        println!("{}", alignment);
+    } else {
+        let clustering = cluster::cluster(&crabs, targets, cost.as_ref())?;
+        for (idx, (target, cost)) in clustering
+            .assignment
+            .iter()
+            .zip(&clustering.costs)
+            .enumerate()
+        {
+            log::debug!(
+                "crab {}: {} fuel to {}",
+                idx,
+                cost,
+                clustering.targets[*target]
+            );
+        }
+        println!("{}", clustering);
+    }
+    Ok(())
+}
+
+/// Where the crabs line up, and what it costs them
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alignment {
+    pub position: i64,
+    pub fuel: i64,
+    /// Fuel spent by each crab, in input order
+    pub costs: Vec<i64>,
+}
+
+impl Alignment {
+    /// Show the alignment at `-v`, and each crab's share at `-vv`
+    fn log(&self) {
+        log::info!("{}", self);
+        for (idx, cost) in self.costs.iter().enumerate() {
+            log::debug!("crab {}: {} fuel", idx, cost);
+        }
+    }
+}
+
+impl std::fmt::Display for Alignment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} crabs align at {} using {} fuel",
+            self.costs.len(),
+            self.position,
+            self.fuel
+        )
+    }
+}
+
+/// The cheapest alignment, using the fastest search `cost` allows
+pub fn align(crabs: &[i64], cost: &dyn CostFunction) -> Result<Alignment> {
+    if let Some(targets) = cost.shortcut(crabs) {
+        min_fuel_among(crabs, targets.into_iter(), cost)
+    } else if cost.is_convex() {
+        convex_search(crabs, cost)
+    } else {
+        find_min_fuel_transfer(crabs, cost)
+    }
+}
+
+/// Try every position between the outermost crabs, for cost functions with
+/// no known shortcut
+fn find_min_fuel_transfer(crabs: &[i64], cost: &dyn CostFunction) -> Result<Alignment> {
+    let (min, max) = minmax(crabs)?;
+    min_fuel_among(crabs, min..=max, cost)
+}
+
+/// Ternary search between the outermost crabs, for a convex total cost
+fn convex_search(crabs: &[i64], cost: &dyn CostFunction) -> Result<Alignment> {
+    let (mut lo, mut hi) = minmax(crabs)?;
+    while hi - lo > 2 {
+        let third = (hi - lo) / 3;
+        let (m1, m2) = (lo + third, hi - third);
+        let (e1, e2) = (wide_total(crabs, m1, cost), wide_total(crabs, m2, cost));
+        if e1 < e2 {
+            hi = m2 - 1;
+        } else if e1 > e2 {
+            lo = m1 + 1;
+        } else {
+            // a convex function is no lower outside of two equal points
+            lo = m1;
+            hi = m2;
+        }
+    }
+    min_fuel_among(crabs, lo..=hi, cost)
+}
+
+fn min_fuel_among(
+    crabs: &[i64],
+    targets: impl Iterator<Item = i64>,
+    cost: &dyn CostFunction,
+) -> Result<Alignment> {
+    let (position, fuel) = targets
+        .map(|t| (t, total_error(crabs, t, cost)))
         .min_by_key(|(_, e)| *e)
         .ok_or_else(|| anyhow::anyhow!("no possible targets"))?;
-    log::debug!("target: {:?}, fuel: {:?}", target, fuel);
-    Ok(fuel)
+    if fuel == i64::MAX {
+        anyhow::bail!("fuel overflows at every target");
+    }
+    let costs = crabs
+        .iter()
+        .enumerate()
+        .map(|(idx, c)| cost.cost(idx, *c, position))
+        .collect();
+    Ok(Alignment {
+        position,
+        fuel,
+        costs,
+    })
+}
+
+/// The lower median, found by selection in O(n)
+fn median(data: &[i64]) -> Result<i64> {
+    if data.is_empty() {
+        anyhow::bail!("can not get median of empty list");
+    }
+    let mut data = data.to_vec();
+    let mid = (data.len() - 1) / 2;
+    let (_, median, _) = data.select_nth_unstable(mid);
+    Ok(*median)
+}
+
+/// Every integer within 1 of the mean
+fn mean_neighbourhood(data: &[i64]) -> Result<impl Iterator<Item = i64>> {
+    if data.is_empty() {
+        anyhow::bail!("can not get mean of empty list");
+    }
+    let sum = data.iter().map(|d| i128::from(*d)).sum::<i128>();
+    let len = data.len() as i128;
+    let floor = sum.div_euclid(len);
+    let ceil = floor + i128::from(sum.rem_euclid(len) != 0);
+    // the mean is between the smallest and largest number, so both fit
+    let (floor, ceil) = (floor as i64, ceil as i64);
+    Ok(floor.saturating_sub(1)..=ceil.saturating_add(1))
 }
 
 fn parse(input: &str) -> Result<Vec<i64>> {
@@ -36,24 +229,43 @@ fn parse(input: &str) -> Result<Vec<i64>> {
         .collect()
 }
 
-fn total_error<F>(data: &[i64], target: i64, err_func: F) -> i64
-where
-    F: Fn(i64, i64) -> i64,
-{
-    data.iter().map(|d| err_func(target, *d).abs()).sum()
+/// Stands in for a saturated cost in a wide total, outweighing anything the
+/// other crabs could save
+const SATURATED: i128 = 1 << 90;
+
+/// One crab's cost, ready to add up without overflowing
+fn widen(cost: i64) -> i128 {
+    if cost == i64::MAX {
+        SATURATED
+    } else {
+        i128::from(cost)
+    }
+}
+
+/// A wide total, saturating at the bounds of an `i64`
+fn narrow(total: i128) -> i64 {
+    i64::try_from(total).unwrap_or(if total > 0 { i64::MAX } else { i64::MIN })
 }
 
-fn linear_error(target: i64, source: i64) -> i64 {
-    (target - source).abs()
+/// Total fuel to reach `target`, saturating at `i64::MAX`
+fn total_error(data: &[i64], target: i64, cost: &dyn CostFunction) -> i64 {
+    narrow(wide_total(data, target, cost))
 }
 
-fn step_error(target: i64, source: i64) -> i64 {
-    let distance = (target - source).abs();
-    arithmetic_progression(distance)
+/// Total fuel to reach `target` without saturating the sum, so totals past
+/// `i64::MAX` can still be compared
+fn wide_total(data: &[i64], target: i64, cost: &dyn CostFunction) -> i128 {
+    data.iter()
+        .enumerate()
+        .map(|(idx, d)| widen(cost.cost(idx, *d, target)))
+        .sum()
 }
 
+/// `1 + 2 + ... + n`, saturating at `i64::MAX`
 fn arithmetic_progression(n: i64) -> i64 {
-    (n * n + n) / 2
+    n.checked_add(1)
+        .and_then(|m| n.checked_mul(m))
+        .map_or(i64::MAX, |x| x / 2)
 }
 
 fn minmax(data: &[i64]) -> Result<(i64, i64)> {
@@ -78,25 +290,296 @@ fn minmax(data: &[i64]) -> Result<(i64, i64)> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::challenge::answers::expected;
+    use cost::Quadratic;
     const INPUT: &str = include_str!("../../../input/day7");
     const EX: &str = include_str!("../../../input/day7_ex");
 
     #[test]
     fn verify_p1() {
-        assert_eq!(part1(INPUT).unwrap().as_str(), "344735")
+        assert_eq!(
+            part1(&parse(INPUT).unwrap()).unwrap().to_string(),
+            expected(7, "day7", 1)
+        )
     }
     #[test]
     fn verify_p2() {
-        assert_eq!(part2(INPUT).unwrap().as_str(), "96798233")
+        assert_eq!(
+            part2(&parse(INPUT).unwrap()).unwrap().to_string(),
+            expected(7, "day7", 2)
+        )
     }
 
     #[test]
     fn check_example_p1() {
-        assert_eq!(part1(EX).unwrap().as_str(), "37")
+        assert_eq!(
+            part1(&parse(EX).unwrap()).unwrap().to_string(),
+            expected(7, "day7_ex", 1)
+        )
     }
     #[test]
     fn check_example_p2() {
-        assert_eq!(part2(EX).unwrap().as_str(), "168")
+        assert_eq!(
+            part2(&parse(EX).unwrap()).unwrap().to_string(),
+            expected(7, "day7_ex", 2)
+        )
+    }
+
+    #[test]
+    fn shortcuts_match_brute_force() {
+        for crabs in [
+            parse(EX).unwrap(),
+            parse(INPUT).unwrap(),
+            vec![0, 1000],
+            vec![-7, 3, 3, 12, 400],
+            vec![5],
+        ] {
+            assert_eq!(
+                part1(&crabs).unwrap(),
+                find_min_fuel_transfer(&crabs, &Linear).unwrap().fuel
+            );
+            assert_eq!(
+                part2(&crabs).unwrap(),
+                find_min_fuel_transfer(&crabs, &Step).unwrap().fuel
+            );
+        }
+    }
+    #[test]
+    fn example_alignment() {
+        let crabs = parse(EX).unwrap();
+        let alignment = find_min_fuel_transfer(&crabs, &Linear).unwrap();
+        assert_eq!(alignment.position, 2);
+        assert_eq!(alignment.fuel, 37);
+        assert_eq!(alignment.costs, vec![14, 1, 0, 2, 2, 0, 5, 1, 0, 12]);
+        let alignment = find_min_fuel_transfer(&crabs, &Step).unwrap();
+        assert_eq!(alignment.position, 5);
+        assert_eq!(alignment.fuel, 168);
+        assert_eq!(alignment.costs.iter().sum::<i64>(), 168);
+    }
+    #[test]
+    fn search_includes_outermost_crabs() {
+        struct TowardsTen;
+        impl CostFunction for TowardsTen {
+            fn cost(&self, _crab: usize, _from: i64, to: i64) -> i64 {
+                10 - to
+            }
+        }
+        let alignment = find_min_fuel_transfer(&[0, 10], &TowardsTen).unwrap();
+        assert_eq!(alignment.position, 10);
+        assert_eq!(alignment.fuel, 0);
+    }
+    #[test]
+    fn convex_search_matches_brute_force() {
+        let crabs = parse(INPUT).unwrap();
+        let weights = (0..crabs.len() as i64).map(|w| w % 7).collect::<Vec<_>>();
+        let costs: Vec<Box<dyn CostFunction>> = vec![
+            Box::new(Quadratic),
+            Box::new("3,0,1".parse::<Polynomial>().unwrap()),
+            Box::new("0,5,0,1".parse::<Polynomial>().unwrap()),
+            Box::new(Weighted {
+                inner: Box::new(Linear),
+                weights: weights.clone(),
+            }),
+            Box::new(Weighted {
+                inner: Box::new(Step),
+                weights,
+            }),
+        ];
+        for cost in costs {
+            assert!(cost.is_convex());
+            assert_eq!(
+                convex_search(&crabs, cost.as_ref()).unwrap().fuel,
+                find_min_fuel_transfer(&crabs, cost.as_ref()).unwrap().fuel
+            );
+        }
+    }
+    #[test]
+    fn plateaus_are_searched() {
+        // every target between the two crabs costs the same
+        let crabs = [0, 0, 100, 100];
+        let alignment = convex_search(&crabs, &Linear).unwrap();
+        assert_eq!(alignment.fuel, 200);
+    }
+    #[test]
+    fn capped_cost() {
+        let crabs = parse(EX).unwrap();
+        let capped = Capped {
+            inner: Box::new(Step),
+            cap: 10,
+        };
+        assert!(!capped.is_convex());
+        let alignment = align(&crabs, &capped).unwrap();
+        assert_eq!(
+            alignment.fuel,
+            find_min_fuel_transfer(&crabs, &capped).unwrap().fuel
+        );
+        assert!(alignment.costs.iter().all(|c| *c <= 10));
+    }
+    /// Every choice of `k` targets between the outermost crabs
+    fn brute_force_cluster(crabs: &[i64], k: usize, cost: &dyn CostFunction) -> i64 {
+        let (min, max) = minmax(crabs).unwrap();
+        let mut best = i64::MAX;
+        let mut targets = vec![min; k];
+        loop {
+            let fuel = crabs
+                .iter()
+                .enumerate()
+                .map(|(idx, c)| {
+                    targets
+                        .iter()
+                        .map(|t| cost.cost(idx, *c, *t))
+                        .min()
+                        .unwrap()
+                })
+                .sum::<i64>();
+            best = best.min(fuel);
+            // next non-decreasing combination
+            match (0..k).rev().find(|i| targets[*i] < max) {
+                Some(i) => {
+                    let t = targets[i] + 1;
+                    targets[i..].iter_mut().for_each(|x| *x = t);
+                }
+                None => return best,
+            }
+        }
+    }
+    #[test]
+    fn cluster_matches_brute_force() {
+        let crabs = parse(EX).unwrap();
+        let capped = Capped {
+            inner: Box::new(Step),
+            cap: 12,
+        };
+        let weighted = Weighted {
+            inner: Box::new(Step),
+            weights: vec![3, 1, 1, 2, 1, 1, 4, 1, 1, 2],
+        };
+        let costs: [&dyn CostFunction; 4] = [&Linear, &Step, &capped, &weighted];
+        for cost in costs {
+            for k in 1..=3 {
+                let clustering = cluster::cluster(&crabs, k, cost).unwrap();
+                assert_eq!(
+                    clustering.fuel,
+                    brute_force_cluster(&crabs, k, cost),
+                    "k {}",
+                    k
+                );
+                assert_eq!(clustering.costs.iter().sum::<i64>(), clustering.fuel);
+                for (idx, target) in clustering.assignment.iter().enumerate() {
+                    let t = clustering.targets[*target];
+                    assert_eq!(clustering.costs[idx], cost.cost(idx, crabs[idx], t));
+                }
+            }
+        }
+    }
+    #[test]
+    fn cluster_divide_and_conquer() {
+        // the convex path must agree with trying every split
+        struct NotConvex(Step);
+        impl CostFunction for NotConvex {
+            fn cost(&self, crab: usize, from: i64, to: i64) -> i64 {
+                self.0.cost(crab, from, to)
+            }
+            fn shortcut(&self, crabs: &[i64]) -> Option<Vec<i64>> {
+                self.0.shortcut(crabs)
+            }
+        }
+        let crabs = parse(INPUT)
+            .unwrap()
+            .into_iter()
+            .take(150)
+            .collect::<Vec<_>>();
+        for k in [2, 3, 5] {
+            assert_eq!(
+                cluster::cluster(&crabs, k, &Step).unwrap().fuel,
+                cluster::cluster(&crabs, k, &NotConvex(Step)).unwrap().fuel,
+                "k {}",
+                k
+            );
+        }
+    }
+    #[test]
+    fn cluster_wide_non_convex() {
+        let capped = Capped {
+            inner: Box::new(Step),
+            cap: 500,
+        };
+        let crabs = parse(INPUT).unwrap();
+        let clustering = cluster::cluster(&crabs, 3, &capped).unwrap();
+        assert_eq!(clustering.costs.iter().sum::<i64>(), clustering.fuel);
+        assert!(cluster::cluster(&[0, 1 << 40], 2, &capped).is_err());
+    }
+    #[test]
+    fn cluster_one_target_per_crab() {
+        let crabs = parse(EX).unwrap();
+        let clustering = cluster::cluster(&crabs, 20, &Step).unwrap();
+        assert_eq!(clustering.fuel, 0);
+        assert_eq!(clustering.targets.len(), crabs.len());
+        assert_eq!(
+            cluster::cluster(&crabs, 1, &Step).unwrap().fuel,
+            part2(&crabs).unwrap()
+        );
+    }
+    #[test]
+    fn polynomial_cost() {
+        let p = "1,2,3".parse::<Polynomial>().unwrap();
+        assert_eq!(p.cost(0, 4, 2), 1 + 2 * 2 + 3 * 4);
+        assert!(!"0,-1,1".parse::<Polynomial>().unwrap().is_convex());
+    }
+    #[test]
+    fn cost_overflow() {
+        // d^40 saturates for any distance over 2
+        let mut coefficients = vec![0; 41];
+        coefficients[40] = 1;
+        let p = Polynomial { coefficients };
+        assert_eq!(p.cost(0, 0, 3), i64::MAX);
+        let alignment = align(&[0, 3, 4], &p).unwrap();
+        assert_eq!((alignment.position, alignment.fuel), (2, (1 << 41) + 1));
+        assert!(cluster::cluster(&[0, 3, 4], 2, &p).is_ok());
+
+        let p = "0,0,0,0,0,0,1".parse::<Polynomial>().unwrap();
+        let e = align(&parse(INPUT).unwrap(), &p).unwrap_err();
+        assert_eq!(e.to_string(), "fuel overflows at every target");
+        assert!(cluster::cluster(&[0, i64::MAX], 1, &Quadratic).is_err());
+        assert_eq!(arithmetic_progression(i64::MAX), i64::MAX);
+
+        // totals past i64::MAX either side of an optimum that fits
+        let p = "0,0,0,0,1".parse::<Polynomial>().unwrap();
+        let alignment = align(&[0, 90000], &p).unwrap();
+        assert_eq!(
+            (alignment.position, alignment.fuel),
+            (45000, 2 * 45000i64.pow(4))
+        );
+    }
+    #[test]
+    fn polynomial_saturation() {
+        // a negative term can not undo a value past i64::MAX
+        let p = "-1,-1,0,0,0,0,0,1".parse::<Polynomial>().unwrap();
+        assert_eq!(p.cost(0, 0, 1000000), i64::MAX);
+        // but an intermediate past i64::MAX is fine if the total is not
+        let p = "5,-4294967296,1".parse::<Polynomial>().unwrap();
+        assert_eq!(p.cost(0, 0, 4294967296), 5);
+        let p = "0,0,-1".parse::<Polynomial>().unwrap();
+        assert_eq!(p.cost(0, 0, i64::MAX), i64::MIN);
+        // nor can another crab's negative cost
+        let p = "-1,-1,0,0,0,0,0,1".parse::<Polynomial>().unwrap();
+        assert_eq!(total_error(&[0, 1000000], 0, &p), i64::MAX);
+        assert!(align(&[0, 1000000], &p).is_err());
+    }
+    #[test]
+    fn extreme_mean() {
+        let crabs = [-i64::MAX, -i64::MAX];
+        assert_eq!(part2(&crabs).unwrap(), 0);
+        let crabs = [i64::MAX, i64::MAX, i64::MAX - 3];
+        assert_eq!(
+            mean_neighbourhood(&crabs).unwrap().collect::<Vec<_>>(),
+            [i64::MAX - 2, i64::MAX - 1, i64::MAX]
+        );
+    }
+    #[test]
+    fn empty_crabs() {
+        assert!(part1(&[]).is_err());
+        assert!(part2(&[]).is_err());
     }
 
     #[test]
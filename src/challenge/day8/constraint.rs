@@ -1,5 +1,136 @@
-use aoc::ac3::Constraint;
-use aoc::ac3::AC3;
-use super::sevensegment::SEGMENTS_LEN;
+// Not built on `aoc::ac3`, whose API is not available to this tree to build against
 use super::sevensegment::SevenSegment;
+use super::sevensegment::SEGMENTS_LEN;
+use anyhow::{Context, Result};
+use std::collections::VecDeque;
+
+const ALL_SEGMENTS: u8 = (1 << SEGMENTS_LEN) - 1;
+
+/// Bitset of the true segments a scrambled wire could still be connected to
+type Domain = u8;
+
+fn segment_mask(s: SevenSegment) -> u8 {
+    s.lit_segments().fold(0, |mask, idx| mask | (1 << idx))
+}
+
+fn domain_values(d: Domain) -> impl Iterator<Item = usize> {
+    (0..SEGMENTS_LEN).filter(move |idx| d & (1 << idx) != 0)
+}
+
+#[derive(Debug)]
+pub struct WireDecoder {
+    /// wire index -> true segment index
+    mapping: [usize; SEGMENTS_LEN],
+}
+
+impl WireDecoder {
+    pub fn solve(patterns: &[SevenSegment]) -> Result<WireDecoder> {
+        let mut domains = [ALL_SEGMENTS; SEGMENTS_LEN];
+        restrict_by_length(&mut domains, patterns);
+        ac3(&mut domains)
+            .with_context(|| format!("no wire mapping satisfies patterns: {:?}", patterns))?;
+        log::trace!("domains after ac3: {:?}", domains);
+
+        let mut mapping = [0; SEGMENTS_LEN];
+        if search(&domains, patterns, 0, 0, &mut mapping) {
+            Ok(WireDecoder { mapping })
+        } else {
+            anyhow::bail!("no wire mapping satisfies patterns: {:?}", patterns)
+        }
+    }
+
+    fn translate(&self, s: SevenSegment) -> SevenSegment {
+        translate(&self.mapping, s)
+    }
+
+    pub fn decode(&self, s: SevenSegment) -> Result<u8> {
+        self.translate(s)
+            .to_digit()
+            .ok_or_else(|| anyhow::anyhow!("{:?} does not decode to a digit", s))
+    }
+}
+
+fn translate(mapping: &[usize; SEGMENTS_LEN], s: SevenSegment) -> SevenSegment {
+    SevenSegment::from_segments(s.lit_segments().map(|w| mapping[w]))
+}
+
+/// Each pattern must be one of the digits with the same number of lit segments,
+/// so its wires can only drive segments used by those digits, and the wires it
+/// does not use can only drive segments those digits leave dark.
+fn restrict_by_length(domains: &mut [Domain; SEGMENTS_LEN], patterns: &[SevenSegment]) {
+    for p in patterns {
+        let len = p.total_lit_segments();
+        let (lit, dark) = (0..10)
+            .map(|d| segment_mask(SevenSegment::from_last_digit(d)))
+            .filter(|m| m.count_ones() as usize == len)
+            .fold((0, 0), |(lit, dark), m| {
+                (lit | m, dark | (!m & ALL_SEGMENTS))
+            });
+        for (wire, domain) in domains.iter_mut().enumerate() {
+            *domain &= if p.is_lit(wire) { lit } else { dark };
+        }
+    }
+}
+
+/// Enforce arc-consistency for the all-different constraint between wires
+fn ac3(domains: &mut [Domain; SEGMENTS_LEN]) -> Result<()> {
+    let mut queue = (0..SEGMENTS_LEN)
+        .flat_map(|x| (0..SEGMENTS_LEN).map(move |y| (x, y)))
+        .filter(|(x, y)| x != y)
+        .collect::<VecDeque<_>>();
+
+    while let Some((x, y)) = queue.pop_front() {
+        if revise(domains, x, y) {
+            if domains[x] == 0 {
+                anyhow::bail!("wire {} has no possible segments", x);
+            }
+            queue.extend(
+                (0..SEGMENTS_LEN)
+                    .filter(|z| *z != x && *z != y)
+                    .map(|z| (z, x)),
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Remove any segment from `x` that would leave `y` with nothing to connect to
+fn revise(domains: &mut [Domain; SEGMENTS_LEN], x: usize, y: usize) -> bool {
+    let dy = domains[y];
+    if dy.count_ones() == 1 && domains[x] & dy != 0 {
+        domains[x] &= !dy;
+        true
+    } else {
+        false
+    }
+}
 
+/// Assign the remaining ambiguous wires, checking the patterns are all
+/// different real digits
+fn search(
+    domains: &[Domain; SEGMENTS_LEN],
+    patterns: &[SevenSegment],
+    wire: usize,
+    used: u8,
+    mapping: &mut [usize; SEGMENTS_LEN],
+) -> bool {
+    if wire == SEGMENTS_LEN {
+        let mut seen = 0u16;
+        return patterns
+            .iter()
+            .all(|p| match translate(mapping, *p).to_digit() {
+                Some(d) if seen & (1 << d) == 0 => {
+                    seen |= 1 << d;
+                    true
+                }
+                _ => false,
+            });
+    }
+    for segment in domain_values(domains[wire] & !used) {
+        mapping[wire] = segment;
+        if search(domains, patterns, wire + 1, used | (1 << segment), mapping) {
+            return true;
+        }
+    }
+    false
+}
@@ -1,27 +1,66 @@
+use super::solution::{Day, Solution};
 use anyhow::{Context, Result};
 use sevensegment::SevenSegment;
+mod constraint;
 mod sevensegment;
 
-pub fn part1(input: &str) -> Result<String> {
-    let observations = parse(input)?;
+/// The ten unique signal patterns, followed by the four output digits
+pub type Observation = (Vec<SevenSegment>, Vec<SevenSegment>);
+
+pub struct Day8;
+
+pub const SOLUTION: Solution = Solution::new::<Day8>();
+
+impl Day for Day8 {
+    const DAY: u32 = 8;
+    const TITLE: &'static str = "Seven Segment Search";
+    const EXAMPLE: &'static str = include_str!("../../../input/day8_ex");
+
+    type Input = Vec<Observation>;
+    type Answer1 = usize;
+    type Answer2 = i64;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        parse(input)
+    }
+    fn part1(observations: &Self::Input) -> Result<usize> {
+        part1(observations)
+    }
+    fn part2(observations: &Self::Input) -> Result<i64> {
+        part2(observations)
+    }
+}
+
+pub fn part1(observations: &[Observation]) -> Result<usize> {
     let unqiue = observations
         .iter()
-        .flat_map(|(i, o)| o.iter())
+        .flat_map(|(_, o)| o.iter())
         .filter_map(|s| sevensegment::guess_digit(*s))
         .count();
-    Ok(format!("{:?}", unqiue))
+    Ok(unqiue)
+}
+
+pub fn part2(observations: &[Observation]) -> Result<i64> {
+    observations
+        .iter()
+        .map(|(digits, numbers)| decode_display(digits, numbers))
+        .sum()
 }
 
-pub fn part2(input: &str) -> Result<String> {
-    let x = 0;
-    Ok(format!("{:?}", x))
+fn decode_display(digits: &[SevenSegment], numbers: &[SevenSegment]) -> Result<i64> {
+    let decoder = constraint::WireDecoder::solve(digits)?;
+    log::trace!("decoder: {:?}", decoder);
+    numbers.iter().try_fold(0, |acc, s| {
+        let d = decoder.decode(*s)?;
+        Ok(acc * 10 + d as i64)
+    })
 }
 
-fn parse(input: &str) -> Result<Vec<(Vec<SevenSegment>, Vec<SevenSegment>)>> {
+fn parse(input: &str) -> Result<Vec<Observation>> {
     input.lines().map(parse_line).collect()
 }
 
-fn parse_line(input: &str) -> Result<(Vec<SevenSegment>, Vec<SevenSegment>)> {
+fn parse_line(input: &str) -> Result<Observation> {
     let mut chunks = input.split('|');
     let digits_chunk = chunks
         .next()
@@ -47,23 +86,63 @@ fn parse_displays(input: &str) -> Result<Vec<SevenSegment>> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::challenge::answers::expected;
     const INPUT: &str = include_str!("../../../input/day8");
     const EX: &str = include_str!("../../../input/day8_ex");
 
     #[test]
     fn verify_p1() {
-        assert_eq!(part1(INPUT).unwrap().as_str(), "303")
+        assert_eq!(
+            part1(&parse(INPUT).unwrap()).unwrap().to_string(),
+            expected(8, "day8", 1)
+        )
     }
     #[test]
     fn verify_p2() {
-        assert_eq!(part2(INPUT).unwrap().as_str(), "0")
+        assert_eq!(
+            part2(&parse(INPUT).unwrap()).unwrap().to_string(),
+            expected(8, "day8", 2)
+        )
     }
     #[test]
     fn check_p1_example() {
-        assert_eq!(part1(EX).unwrap().as_str(), "26")
+        assert_eq!(
+            part1(&parse(EX).unwrap()).unwrap().to_string(),
+            expected(8, "day8_ex", 1)
+        )
     }
     #[test]
     fn check_p2_example() {
-        assert_eq!(part2(EX).unwrap().as_str(), "0")
+        assert_eq!(
+            part2(&parse(EX).unwrap()).unwrap().to_string(),
+            expected(8, "day8_ex", 2)
+        )
+    }
+    #[test]
+    fn decode_worked_example() {
+        let (digits, numbers) = parse_line(
+            "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | \
+             cdfeb fcadb cdfeb cdbaf",
+        )
+        .unwrap();
+        let decoder = constraint::WireDecoder::solve(&digits).unwrap();
+        let decoded = digits
+            .iter()
+            .map(|s| decoder.decode(*s).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(decoded, [8, 5, 2, 3, 7, 9, 6, 4, 0, 1]);
+        assert_eq!(decode_display(&digits, &numbers).unwrap(), 5353);
+    }
+    #[test]
+    fn contradictory_patterns() {
+        let no_mapping = |line: &str| {
+            let (digits, _) = parse_line(line).unwrap();
+            let e = constraint::WireDecoder::solve(&digits).unwrap_err();
+            assert!(e.to_string().starts_with("no wire mapping"), "{:#}", e);
+        };
+        // two different patterns for the only two-segment digit
+        no_mapping("acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb cd | cd");
+        // every pattern is possible on its own, but 2, 3 and 5 all repeat one
+        no_mapping("acedgfb cdfbe cdfbe cdfbe dab cefabd cdfgeb eafb cagedb ab | ab");
     }
 }
@@ -11,7 +11,7 @@ pub const SEGMENTS_LEN: usize = 7;
     e    f
      gggg
 */
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
 pub struct SevenSegment([bool; SEGMENTS_LEN]);
 
 fn wire_name_to_pos(c: char) -> Result<usize> {
@@ -43,6 +43,13 @@ impl SevenSegment {
             _ => unreachable!("invalid digit"),
         })
     }
+    pub fn from_segments(segments: impl Iterator<Item = usize>) -> SevenSegment {
+        let mut disp = SevenSegment::default();
+        for idx in segments {
+            disp.0[idx] = true
+        }
+        disp
+    }
     pub fn from_wire_code(code: &str) -> Result<SevenSegment> {
         let mut disp = SevenSegment::default();
         for idx in code.chars().map(wire_name_to_pos) {
@@ -51,15 +58,21 @@ impl SevenSegment {
         }
         Ok(disp)
     }
-    fn lit_segments(&self) -> impl Iterator<Item = usize> + '_ {
+    pub fn lit_segments(&self) -> impl Iterator<Item = usize> + '_ {
         self.0
             .iter()
             .enumerate()
             .filter_map(|(idx, s)| if *s { Some(idx) } else { None })
     }
-    fn total_lit_segments(self) -> usize {
+    pub fn total_lit_segments(self) -> usize {
         self.lit_segments().count()
     }
+    pub fn is_lit(self, idx: usize) -> bool {
+        self.0[idx]
+    }
+    pub fn to_digit(self) -> Option<u8> {
+        (0..10).find(|d| SevenSegment::from_last_digit(*d) == self)
+    }
 }
 
 pub fn guess_digit(s: SevenSegment) -> Option<i64> {
@@ -0,0 +1,208 @@
+use super::Solution;
+use anyhow::{Context, Result};
+use std::{
+    fs,
+    io::{self, BufRead, BufReader, Read},
+    path::{Path, PathBuf},
+};
+
+pub const STDIN_ARG: &str = "-";
+
+/// The file name we expect a day's input to be stored under, e.g. `day4_ex`
+pub fn input_name(day: u32, example: bool) -> String {
+    if example {
+        format!("day{}_ex", day)
+    } else {
+        format!("day{}", day)
+    }
+}
+
+fn search_dirs() -> Vec<PathBuf> {
+    vec![
+        PathBuf::from("input"),
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("input"),
+    ]
+}
+
+pub fn find_input(day: u32, example: bool) -> Result<PathBuf> {
+    let name = input_name(day, example);
+    let candidates = search_dirs()
+        .into_iter()
+        .map(|d| d.join(&name))
+        .collect::<Vec<_>>();
+    candidates
+        .iter()
+        .find(|p| p.is_file())
+        .cloned()
+        .ok_or_else(|| {
+            let searched = candidates
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect::<Vec<_>>();
+            anyhow::anyhow!(
+                "no input for day {}, searched: {}",
+                day,
+                searched.join(", ")
+            )
+        })
+}
+
+/// An opened, but not yet read, puzzle input
+pub struct InputSource {
+    /// Name used to look the input up in the answer ledger, if it has one
+    pub name: Option<String>,
+    /// Where the input was read from, `-` for stdin
+    pub path: Option<String>,
+    pub reader: Box<dyn BufRead>,
+}
+
+#[derive(Debug)]
+pub struct PuzzleInput {
+    pub name: Option<String>,
+    pub path: Option<String>,
+    pub data: String,
+}
+
+fn file_name(path: &Path) -> Option<String> {
+    path.file_name().map(|n| n.to_string_lossy().into_owned())
+}
+
+/// The ledger name for an explicit `path`, only if it is one of the
+/// conventional input files. Any other file that happens to be called `dayN`
+/// has no known answers.
+fn ledger_name(path: &Path) -> Option<String> {
+    let name = file_name(path)?;
+    let target = fs::canonicalize(path).ok()?;
+    search_dirs()
+        .into_iter()
+        .any(|d| fs::canonicalize(d.join(&name)).ok() == Some(target.clone()))
+        .then_some(name)
+}
+
+fn open_file<P: AsRef<Path>>(path: P) -> Result<Box<dyn BufRead>> {
+    log::trace!("Opening file: {}", path.as_ref().display());
+    let f = fs::File::open(&path)
+        .with_context(|| format!("Unable to open path: {}", path.as_ref().display()))?;
+    Ok(Box::new(BufReader::new(f)))
+}
+
+/// Open the input for `solution`, either from an explicit path (`-` for stdin)
+/// or from the conventional `input/dayN` location.
+pub fn open(path: Option<&str>, solution: &Solution, example: bool) -> Result<InputSource> {
+    match path {
+        Some(STDIN_ARG) => Ok(InputSource {
+            name: None,
+            path: Some(STDIN_ARG.to_string()),
+            reader: Box::new(BufReader::new(io::stdin())),
+        }),
+        Some(p) => Ok(InputSource {
+            name: ledger_name(Path::new(p)),
+            path: Some(p.to_string()),
+            reader: open_file(p)?,
+        }),
+        None => match find_input(solution.day, example) {
+            Ok(p) => Ok(InputSource {
+                name: file_name(&p),
+                path: Some(p.display().to_string()),
+                reader: open_file(&p)?,
+            }),
+            Err(e) if example => {
+                log::debug!("{}, using built-in example", e);
+                Ok(InputSource {
+                    name: Some(input_name(solution.day, example)),
+                    path: None,
+                    reader: Box::new(solution.example.as_bytes()),
+                })
+            }
+            Err(e) => Err(e),
+        },
+    }
+}
+
+/// Read the whole input into memory
+pub fn load(path: Option<&str>, solution: &Solution, example: bool) -> Result<PuzzleInput> {
+    let mut source = open(path, solution, example)?;
+    let mut data = String::new();
+    source.reader.read_to_string(&mut data).with_context(|| {
+        format!(
+            "Unable to read input: {}",
+            source.path.as_deref().unwrap_or("example")
+        )
+    })?;
+    Ok(PuzzleInput {
+        name: source.name,
+        path: source.path,
+        data,
+    })
+}
+
+/// Iterate over the lines of `reader` without holding more than one in memory
+pub fn lines(reader: &mut dyn BufRead) -> impl Iterator<Item = Result<String>> + '_ {
+    reader
+        .lines()
+        .map(|l| l.context("Unable to read line of input"))
+}
+
+/// Feed the `Ok` items of `iter` to `f`, stopping at the first error and returning it
+pub fn process_results<T, I, F, R>(iter: I, f: F) -> Result<R>
+where
+    I: Iterator<Item = Result<T>>,
+    F: FnOnce(&mut dyn Iterator<Item = T>) -> R,
+{
+    let mut error = Ok(());
+    let mut shunt = iter.scan(&mut error, |error, item| match item {
+        Ok(x) => Some(x),
+        Err(e) => {
+            **error = Err(e);
+            None
+        }
+    });
+    let result = f(&mut shunt);
+    error.map(|_| result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_real_input() {
+        assert!(find_input(1, false).unwrap().ends_with("input/day1"))
+    }
+    #[test]
+    fn find_example_input() {
+        assert!(find_input(1, true).unwrap().ends_with("input/day1_ex"))
+    }
+    #[test]
+    fn process_results_stops_at_error() {
+        let items = vec![Ok(1), Ok(2), Err(anyhow::anyhow!("bad")), Ok(3)];
+        let mut seen = Vec::new();
+        let result = process_results(items.into_iter(), |iter| seen.extend(iter));
+        assert!(result.is_err());
+        assert_eq!(seen, vec![1, 2]);
+    }
+    #[test]
+    fn process_results_passes_through() {
+        let items = vec![Ok(1), Ok(2), Ok(3)];
+        let sum = process_results(items.into_iter(), |iter| iter.sum::<i32>()).unwrap();
+        assert_eq!(sum, 6);
+    }
+    #[test]
+    fn ledger_name_only_for_conventional_inputs() {
+        let conventional = find_input(1, false).unwrap();
+        assert_eq!(ledger_name(&conventional), Some("day1".to_string()));
+
+        let dir = std::env::temp_dir().join(format!("ledger_name_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let elsewhere = dir.join("day1");
+        fs::write(&elsewhere, "1\n2\n3\n").unwrap();
+        let name = ledger_name(&elsewhere);
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(name, None);
+    }
+    #[test]
+    fn missing_input_lists_searched_paths() {
+        let e = find_input(99, false).unwrap_err().to_string();
+        assert!(e.contains("input/day99"), "{}", e)
+    }
+}
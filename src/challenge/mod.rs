@@ -1,6 +1,7 @@
 use anyhow::{anyhow as ah, Context, Result};
-use std::{fs, io::Read, path};
 
+mod answers;
+mod bench;
 mod day1;
 mod day2;
 mod day3;
@@ -9,42 +10,152 @@ mod day5;
 mod day6;
 mod day7;
 mod day8;
+mod input;
+mod runner;
+mod scaffold;
+mod solution;
+
+pub use solution::Solution;
+
+pub const SOLUTIONS: &[Solution] = &[
+    day1::SOLUTION,
+    day2::SOLUTION,
+    day3::SOLUTION,
+    day4::SOLUTION,
+    day5::SOLUTION,
+    day6::SOLUTION,
+    day7::SOLUTION,
+    day8::SOLUTION,
+];
+
+pub fn lookup(day: u32) -> Option<&'static Solution> {
+    SOLUTIONS.iter().find(|s| s.day == day)
+}
 
 pub fn run(args: &clap::ArgMatches) -> Result<()> {
-    let day = args.value_of("day").unwrap().parse::<u32>()?;
-    let part = args.value_of("part").unwrap().parse::<u32>()?;
-    let input = read_to_string(args.value_of("input").unwrap())?;
-    log::debug!("running day {}:{}", day, part);
-    let result = match (day, part) {
-        (1, 1) => day1::part1(&input),
-        (1, 2) => day1::part2(&input),
-        (2, 1) => day2::part1(&input),
-        (2, 2) => day2::part2(&input),
-        (3, 1) => day3::part1(&input),
-        (3, 2) => day3::part2(&input),
-        (4, 1) => day4::part1(&input),
-        (4, 2) => day4::part2(&input),
-        (5, 1) => day5::part1(&input),
-        (5, 2) => day5::part2(&input),
-        (6, 1) => day6::part1(&input),
-        (6, 2) => day6::part2(&input),
-        (7, 1) => day7::part1(&input),
-        (7, 2) => day7::part2(&input),
-        (8, 1) => day8::part1(&input),
-        (8, 2) => day8::part2(&input),
-        (d, p) => Err(ah!("unimplemented challenge day {} part {}", d, p)),
-    }?;
-    println!("{}", result);
+    if let ("bench", Some(sub)) = args.subcommand() {
+        return bench::run(sub);
+    }
+    if let ("vents", Some(sub)) = args.subcommand() {
+        return day5::vents(sub);
+    }
+    if let ("fish", Some(sub)) = args.subcommand() {
+        return day6::simulate(sub);
+    }
+    if let ("crabs", Some(sub)) = args.subcommand() {
+        return day7::simulate(sub);
+    }
+    if let ("new-day", Some(sub)) = args.subcommand() {
+        let day = sub.value_of("day").unwrap().parse::<u32>()?;
+        return scaffold::new_day(day, sub.value_of("title").unwrap_or(""));
+    }
+    if args.is_present("list") {
+        for s in SOLUTIONS {
+            println!("{:>2}: {}", s.day, s.title);
+        }
+        return Ok(());
+    }
+    let mut ledger = answers::Ledger::load(answers::ledger_path())?;
+    let format = args
+        .value_of("format")
+        .unwrap_or("text")
+        .parse::<runner::Format>()?;
+    let example = args.is_present("example");
+
+    let reports = if args.is_present("all") {
+        runner::run_all(example, &ledger)
+    } else {
+        let day = args.value_of("day").unwrap().parse::<u32>()?;
+        let part = args.value_of("part").unwrap().parse::<u32>()?;
+        log::debug!("running day {}:{}", day, part);
+        let solution = lookup(day).ok_or_else(|| ah!("unimplemented challenge day {}", day))?;
+        if solution.part(part).is_none() {
+            return Err(ah!("unimplemented challenge day {} part {}", day, part));
+        }
+        let path = args.value_of("input");
+        if args.is_present("stream") && solution.stream.is_some() {
+            let source = input::open(path, solution, example);
+            vec![runner::run_stream(solution, source, part, &ledger)]
+        } else {
+            if args.is_present("stream") {
+                log::info!("day {} cannot stream its input, reading it all", day);
+            }
+            let input = input::load(path, solution, example);
+            vec![runner::run_input(solution, input, &[part], &ledger)]
+        }
+    };
+
+    if args.is_present("record") {
+        record_new_answers(&mut ledger, &reports)?;
+    }
+
+    match format {
+        runner::Format::Json => runner::print_json(&reports)?,
+        runner::Format::Text if args.is_present("all") => runner::print_table(&reports),
+        runner::Format::Text => {
+            for d in &reports {
+                for r in &d.parts {
+                    let answer = r.answer.as_ref().map_err(|e| ah!("{:#}", e))?;
+                    println!("{}", answer);
+                    if let (Some(name), Some(verdict)) = (&d.input, &r.verdict) {
+                        eprintln!("{} day {} part {}: {}", name, d.day, r.part, verdict);
+                    }
+                }
+            }
+        }
+    }
+
+    let parts = reports.iter().flat_map(|d| d.parts.iter());
+    let failed = parts.clone().filter(|r| !r.passed()).count();
+    if failed > 0 {
+        return Err(ah!("{} of {} parts failed", failed, parts.count()));
+    }
     Ok(())
 }
 
-fn read_to_string<P: AsRef<path::Path>>(path: P) -> Result<String> {
-    log::trace!("Reading content of file: {}", path.as_ref().display());
-    let mut f = fs::File::open(&path)
-        .with_context(|| format!("Unable to open path: {}", path.as_ref().display()))?;
+/// Parse an optional argument, falling back to `default` when it is absent
+fn parse_arg<T: std::str::FromStr>(args: &clap::ArgMatches, name: &str, default: T) -> Result<T>
+where
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    args.value_of(name)
+        .map(|v| {
+            v.parse::<T>()
+                .with_context(|| format!("could not parse {}: {:?}", name, v))
+        })
+        .unwrap_or(Ok(default))
+}
+
+fn record_new_answers(ledger: &mut answers::Ledger, reports: &[runner::DayReport]) -> Result<()> {
+    let mut changed = false;
+    for d in reports {
+        for r in &d.parts {
+            if let (Some(name), Ok(answer), Some(answers::Verdict::New)) =
+                (&d.input, &r.answer, &r.verdict)
+            {
+                ledger.record(d.day, name, r.part, answer);
+                changed = true;
+            }
+        }
+    }
+    if changed {
+        ledger.save(answers::ledger_path())?;
+    }
+    Ok(())
+}
 
-    let mut result = String::new();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    f.read_to_string(&mut result)?;
-    Ok(result)
+    #[test]
+    fn solutions_are_ordered_by_day() {
+        let days = SOLUTIONS.iter().map(|s| s.day).collect::<Vec<_>>();
+        let expected = (1..=SOLUTIONS.len() as u32).collect::<Vec<_>>();
+        assert_eq!(days, expected)
+    }
+    #[test]
+    fn lookup_missing_day() {
+        assert!(lookup(0).is_none())
+    }
 }
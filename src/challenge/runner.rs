@@ -0,0 +1,280 @@
+use super::{
+    answers::{Ledger, Verdict},
+    input::{self, InputSource, PuzzleInput},
+    Solution, SOLUTIONS,
+};
+use anyhow::Result;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+pub const PARTS: [u32; 2] = [1, 2];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+impl std::str::FromStr for Format {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "text" => Format::Text,
+            "json" => Format::Json,
+            _ => anyhow::bail!("unknown output format: {:?}", s),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct DayReport {
+    pub day: u32,
+    pub input: Option<String>,
+    pub path: Option<String>,
+    /// Time spent parsing, shared by both parts
+    pub parse: Duration,
+    pub parts: Vec<PartReport>,
+}
+
+#[derive(Debug)]
+pub struct PartReport {
+    pub part: u32,
+    pub solve: Duration,
+    pub answer: Result<String>,
+    pub verdict: Option<Verdict>,
+}
+
+impl DayReport {
+    pub fn elapsed(&self) -> Duration {
+        self.parse + self.parts.iter().map(|p| p.solve).sum::<Duration>()
+    }
+}
+
+impl PartReport {
+    pub fn passed(&self) -> bool {
+        self.answer.is_ok() && !matches!(self.verdict, Some(Verdict::Differs(_)))
+    }
+    fn status(&self) -> &'static str {
+        match (&self.answer, &self.verdict) {
+            (Err(_), _) => "FAIL",
+            (Ok(_), Some(Verdict::Differs(_))) => "WRONG",
+            (Ok(_), Some(Verdict::Match)) => "ok",
+            (Ok(_), Some(Verdict::New)) | (Ok(_), None) => "new",
+        }
+    }
+}
+
+/// Copy an error, keeping each message in its chain
+fn duplicate(e: &anyhow::Error) -> anyhow::Error {
+    let mut chain = e
+        .chain()
+        .map(|c| c.to_string())
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev();
+    let root = anyhow::Error::msg(chain.next().unwrap_or_default());
+    chain.fold(root, |err, msg| err.context(msg))
+}
+
+pub fn run_solution(solution: &Solution, example: bool, ledger: &Ledger) -> DayReport {
+    run_input(
+        solution,
+        input::load(None, solution, example),
+        &PARTS,
+        ledger,
+    )
+}
+
+pub fn run_input(
+    solution: &Solution,
+    input: Result<PuzzleInput>,
+    parts: &[u32],
+    ledger: &Ledger,
+) -> DayReport {
+    let name = input.as_ref().ok().and_then(|i| i.name.clone());
+    let path = input.as_ref().ok().and_then(|i| i.path.clone());
+
+    let start = Instant::now();
+    let parsed = input.and_then(|i| (solution.parse)(&i.data));
+    let parse = start.elapsed();
+    log::debug!("day {} parsed in {:?}", solution.day, parse);
+
+    let parts = parts
+        .iter()
+        .map(|&part| {
+            let start = Instant::now();
+            let answer = match (&parsed, solution.part(part)) {
+                (Ok(parsed), Some(solve)) => solve(parsed.as_ref()),
+                (Err(e), _) => Err(duplicate(e)),
+                (_, None) => Err(anyhow::anyhow!("no part {}", part)),
+            };
+            let solve = start.elapsed();
+            log::debug!("day {}:{} solved in {:?}", solution.day, part, solve);
+            part_report(solution, name.as_deref(), part, solve, answer, ledger)
+        })
+        .collect();
+
+    DayReport {
+        day: solution.day,
+        input: name,
+        path,
+        parse,
+        parts,
+    }
+}
+
+fn part_report(
+    solution: &Solution,
+    name: Option<&str>,
+    part: u32,
+    solve: Duration,
+    answer: Result<String>,
+    ledger: &Ledger,
+) -> PartReport {
+    let verdict = match (name, &answer) {
+        (Some(name), Ok(answer)) => Some(ledger.check(solution.day, name, part, answer)),
+        _ => None,
+    };
+    PartReport {
+        part,
+        solve,
+        answer,
+        verdict,
+    }
+}
+
+/// Solve a single part straight from `source`, one line at a time. Parsing
+/// happens as part of solving, so the report's parse time is always zero.
+pub fn run_stream(
+    solution: &Solution,
+    source: Result<InputSource>,
+    part: u32,
+    ledger: &Ledger,
+) -> DayReport {
+    let name = source.as_ref().ok().and_then(|s| s.name.clone());
+    let path = source.as_ref().ok().and_then(|s| s.path.clone());
+
+    let start = Instant::now();
+    let answer = match (source, solution.stream_part(part)) {
+        (Ok(mut source), Some(stream)) => stream(source.reader.as_mut()),
+        (Err(e), _) => Err(e),
+        (_, None) => Err(anyhow::anyhow!(
+            "day {} cannot stream its input",
+            solution.day
+        )),
+    };
+    let solve = start.elapsed();
+    log::debug!("day {}:{} streamed in {:?}", solution.day, part, solve);
+
+    DayReport {
+        day: solution.day,
+        input: name.clone(),
+        path,
+        parse: Duration::default(),
+        parts: vec![part_report(
+            solution,
+            name.as_deref(),
+            part,
+            solve,
+            answer,
+            ledger,
+        )],
+    }
+}
+
+pub fn run_all(example: bool, ledger: &Ledger) -> Vec<DayReport> {
+    SOLUTIONS
+        .iter()
+        .map(|s| run_solution(s, example, ledger))
+        .collect()
+}
+
+pub fn print_table(reports: &[DayReport]) {
+    println!(
+        "{:>3} {:>4} {:>12} {:>12} {:>6}  answer",
+        "day", "part", "parse", "solve", "status"
+    );
+    for d in reports {
+        for r in &d.parts {
+            let answer = match (&r.answer, &r.verdict) {
+                (Ok(a), Some(Verdict::Differs(expected))) => {
+                    format!("{} (expected {})", a, expected)
+                }
+                (Ok(a), _) => a.clone(),
+                (Err(e), _) => format!("{:#}", e),
+            };
+            println!(
+                "{:>3} {:>4} {:>12} {:>12} {:>6}  {}",
+                d.day,
+                r.part,
+                format!("{:.3?}", d.parse),
+                format!("{:.3?}", r.solve),
+                r.status(),
+                answer
+            );
+        }
+    }
+    let total = reports.iter().map(|d| d.elapsed()).sum::<Duration>();
+    let parts = reports.iter().flat_map(|d| d.parts.iter());
+    let failed = parts.clone().filter(|r| !r.passed()).count();
+    println!(
+        "{} parts, {} failed, {:.3?} total",
+        parts.count(),
+        failed,
+        total
+    );
+}
+
+/// A single part's result, as emitted by `--format json`
+#[derive(Debug, Serialize)]
+struct JsonRecord<'a> {
+    day: u32,
+    part: u32,
+    input: Option<&'a str>,
+    answer: Option<&'a str>,
+    verdict: Option<String>,
+    parse_ns: u64,
+    solve_ns: u64,
+    error: Option<Vec<String>>,
+}
+
+fn json_records(report: &DayReport) -> impl Iterator<Item = JsonRecord<'_>> {
+    report.parts.iter().map(move |r| JsonRecord {
+        day: report.day,
+        part: r.part,
+        input: report.path.as_deref(),
+        answer: r.answer.as_deref().ok(),
+        verdict: r.verdict.as_ref().map(|v| v.to_string()),
+        parse_ns: report.parse.as_nanos() as u64,
+        solve_ns: r.solve.as_nanos() as u64,
+        error: r
+            .answer
+            .as_ref()
+            .err()
+            .map(|e| e.chain().map(|c| c.to_string()).collect()),
+    })
+}
+
+/// Print one JSON object per line for every part that was run
+pub fn print_json(reports: &[DayReport]) -> Result<()> {
+    for record in reports.iter().flat_map(json_records) {
+        println!("{}", serde_json::to_string(&record)?);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duplicate_keeps_chain() {
+        let e = anyhow::anyhow!("root").context("middle").context("outer");
+        let copy = duplicate(&e);
+        assert_eq!(
+            copy.chain().map(|c| c.to_string()).collect::<Vec<_>>(),
+            vec!["outer", "middle", "root"]
+        );
+    }
+}
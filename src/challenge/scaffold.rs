@@ -0,0 +1,151 @@
+use super::{input::input_name, lookup};
+use anyhow::{Context, Result};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+const TEMPLATE: &str = include_str!("template/mod.rs");
+const TEMPLATE_DAY: &str = "day0";
+
+fn source_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).to_path_buf()
+}
+
+/// The template module, with every reference to day 0 pointed at `day`
+pub fn render_template(day: u32, title: &str) -> String {
+    TEMPLATE
+        .replace(TEMPLATE_DAY, &format!("day{}", day))
+        .replace("Day0", &format!("Day{}", day))
+        .replace("const DAY: u32 = 0;", &format!("const DAY: u32 = {};", day))
+        .replace(
+            "const TITLE: &'static str = \"\";",
+            &format!("const TITLE: &'static str = {:?};", title),
+        )
+}
+
+fn mod_day_number(line: &str) -> Option<u32> {
+    line.trim()
+        .strip_prefix("mod day")?
+        .strip_suffix(';')?
+        .parse()
+        .ok()
+}
+
+fn solution_day_number(line: &str) -> Option<u32> {
+    line.trim()
+        .strip_prefix("day")?
+        .strip_suffix("::SOLUTION,")?
+        .parse()
+        .ok()
+}
+
+/// Add `mod dayN;` and `dayN::SOLUTION` to the source of `challenge/mod.rs`
+pub fn register(source: &str, day: u32) -> Result<String> {
+    let mut lines = source.lines().map(String::from).collect::<Vec<_>>();
+    if lines.iter().any(|l| mod_day_number(l) == Some(day)) {
+        anyhow::bail!("day {} is already registered", day);
+    }
+
+    // `mod` items are kept in the same order rustfmt would sort them
+    let mod_name = format!("day{}", day);
+    let mods = lines
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, l)| Some((idx, l.strip_prefix("mod ")?.strip_suffix(';')?)))
+        .collect::<Vec<_>>();
+    let mod_idx = mods
+        .iter()
+        .find(|(_, name)| *name > mod_name.as_str())
+        .map(|(idx, _)| *idx)
+        .or_else(|| mods.last().map(|(idx, _)| idx + 1))
+        .ok_or_else(|| anyhow::anyhow!("could not find any modules"))?;
+    lines.insert(mod_idx, format!("mod {};", mod_name));
+
+    let solutions = lines
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, l)| solution_day_number(l).map(|d| (idx, d)))
+        .collect::<Vec<_>>();
+    let solution_idx = solutions
+        .iter()
+        .find(|(_, d)| *d > day)
+        .map(|(idx, _)| *idx)
+        .or_else(|| solutions.last().map(|(idx, _)| idx + 1))
+        .ok_or_else(|| anyhow::anyhow!("could not find the solution registry"))?;
+    lines.insert(solution_idx, format!("    day{}::SOLUTION,", day));
+
+    let mut result = lines.join("\n");
+    result.push('\n');
+    Ok(result)
+}
+
+fn create_empty(path: &Path) -> Result<()> {
+    if path.exists() {
+        log::info!("{} already exists, leaving it alone", path.display());
+        return Ok(());
+    }
+    fs::write(path, "").with_context(|| format!("Unable to write path: {}", path.display()))
+}
+
+pub fn new_day(day: u32, title: &str) -> Result<()> {
+    let root = source_root();
+    let challenge_dir = root.join("src").join("challenge");
+    let module_dir = challenge_dir.join(format!("day{}", day));
+    if lookup(day).is_some() || module_dir.exists() {
+        anyhow::bail!("day {} already exists", day);
+    }
+
+    let registry_path = challenge_dir.join("mod.rs");
+    let registry = fs::read_to_string(&registry_path)
+        .with_context(|| format!("Unable to open path: {}", registry_path.display()))?;
+    let registry = register(&registry, day)?;
+
+    fs::create_dir(&module_dir)
+        .with_context(|| format!("Unable to create dir: {}", module_dir.display()))?;
+    let module_path = module_dir.join("mod.rs");
+    fs::write(&module_path, render_template(day, title))
+        .with_context(|| format!("Unable to write path: {}", module_path.display()))?;
+    fs::write(&registry_path, registry)
+        .with_context(|| format!("Unable to write path: {}", registry_path.display()))?;
+
+    let input_dir = root.join("input");
+    create_empty(&input_dir.join(input_name(day, false)))?;
+    create_empty(&input_dir.join(input_name(day, true)))?;
+
+    println!("created {}", module_path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    const REGISTRY: &str = include_str!("mod.rs");
+
+    #[test]
+    fn template_points_at_new_day() {
+        let rendered = render_template(9, "Smoke Basin");
+        assert!(rendered.contains("pub struct Day9;"));
+        assert!(rendered.contains("const DAY: u32 = 9;"));
+        assert!(rendered.contains("const TITLE: &'static str = \"Smoke Basin\";"));
+        assert!(rendered.contains("include_str!(\"../../../input/day9_ex\")"));
+        assert!(rendered.contains("include_str!(\"../../../input/day9\")"));
+        assert!(!rendered.contains("day0"));
+    }
+    #[test]
+    fn register_next_day() {
+        let registered = register(REGISTRY, 9).unwrap();
+        assert!(registered.contains("mod day8;\nmod day9;\n"));
+        assert!(registered.contains("    day8::SOLUTION,\n    day9::SOLUTION,\n"));
+    }
+    #[test]
+    fn register_sorts_like_rustfmt() {
+        let registered = register(REGISTRY, 10).unwrap();
+        assert!(registered.contains("mod day1;\nmod day10;\nmod day2;\n"));
+        assert!(registered.contains("    day8::SOLUTION,\n    day10::SOLUTION,\n"));
+    }
+    #[test]
+    fn register_existing_day() {
+        assert!(register(REGISTRY, 1).is_err());
+    }
+}
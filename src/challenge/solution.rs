@@ -0,0 +1,125 @@
+use anyhow::{anyhow as ah, Result};
+use std::{any::Any, fmt, io::BufRead};
+
+/// A single day's puzzle, split into a parse step shared by both parts and
+/// a solve step per part.
+pub trait Day {
+    const DAY: u32;
+    const TITLE: &'static str;
+    const EXAMPLE: &'static str;
+
+    type Input: 'static;
+    type Answer1: fmt::Display;
+    type Answer2: fmt::Display;
+
+    fn parse(input: &str) -> Result<Self::Input>;
+    fn part1(input: &Self::Input) -> Result<Self::Answer1>;
+    fn part2(input: &Self::Input) -> Result<Self::Answer2>;
+}
+
+/// A day that can also solve its parts one line at a time, without reading
+/// the whole input into memory first.
+pub trait Streaming: Day {
+    fn stream_part1(reader: &mut dyn BufRead) -> Result<Self::Answer1>;
+    fn stream_part2(reader: &mut dyn BufRead) -> Result<Self::Answer2>;
+}
+
+pub type Parsed = Box<dyn Any>;
+pub type ParseFn = fn(&str) -> Result<Parsed>;
+pub type SolveFn = fn(&dyn Any) -> Result<String>;
+pub type StreamFn = fn(&mut dyn BufRead) -> Result<String>;
+
+/// Everything the runner needs to know about a single day, with the
+/// day's input and answer types erased
+#[derive(Clone, Copy)]
+pub struct Solution {
+    pub day: u32,
+    pub title: &'static str,
+    pub parse: ParseFn,
+    pub part1: SolveFn,
+    pub part2: SolveFn,
+    /// Streaming versions of part 1 and 2, if the day has them
+    pub stream: Option<(StreamFn, StreamFn)>,
+    pub example: &'static str,
+}
+
+fn parse_erased<D: Day>(input: &str) -> Result<Parsed> {
+    D::parse(input).map(|i| Box::new(i) as Parsed)
+}
+
+fn downcast<D: Day>(input: &dyn Any) -> Result<&D::Input> {
+    input
+        .downcast_ref::<D::Input>()
+        .ok_or_else(|| ah!("day {} was given input for another day", D::DAY))
+}
+
+fn part1_erased<D: Day>(input: &dyn Any) -> Result<String> {
+    D::part1(downcast::<D>(input)?).map(|a| a.to_string())
+}
+
+fn part2_erased<D: Day>(input: &dyn Any) -> Result<String> {
+    D::part2(downcast::<D>(input)?).map(|a| a.to_string())
+}
+
+fn stream1_erased<D: Streaming>(reader: &mut dyn BufRead) -> Result<String> {
+    D::stream_part1(reader).map(|a| a.to_string())
+}
+
+fn stream2_erased<D: Streaming>(reader: &mut dyn BufRead) -> Result<String> {
+    D::stream_part2(reader).map(|a| a.to_string())
+}
+
+impl Solution {
+    pub const fn new<D: Day>() -> Solution {
+        Solution {
+            day: D::DAY,
+            title: D::TITLE,
+            parse: parse_erased::<D>,
+            part1: part1_erased::<D>,
+            part2: part2_erased::<D>,
+            stream: None,
+            example: D::EXAMPLE,
+        }
+    }
+
+    pub const fn streaming<D: Streaming>() -> Solution {
+        let mut solution = Solution::new::<D>();
+        solution.stream = Some((stream1_erased::<D>, stream2_erased::<D>));
+        solution
+    }
+
+    pub fn part(&self, part: u32) -> Option<SolveFn> {
+        match part {
+            1 => Some(self.part1),
+            2 => Some(self.part2),
+            _ => None,
+        }
+    }
+
+    pub fn stream_part(&self, part: u32) -> Option<StreamFn> {
+        match (part, self.stream) {
+            (1, Some((s, _))) => Some(s),
+            (2, Some((_, s))) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Parse `input` and solve a single part of it
+    #[cfg(test)]
+    pub fn solve(&self, part: u32, input: &str) -> Result<String> {
+        let solve = self
+            .part(part)
+            .ok_or_else(|| ah!("unimplemented challenge day {} part {}", self.day, part))?;
+        let parsed = (self.parse)(input)?;
+        solve(parsed.as_ref())
+    }
+}
+
+impl fmt::Debug for Solution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Solution")
+            .field("day", &self.day)
+            .field("title", &self.title)
+            .finish()
+    }
+}
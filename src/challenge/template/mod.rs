@@ -1,13 +1,36 @@
+use super::solution::{Day, Solution};
 use anyhow::{Context, Result};
 
-pub fn part1(input: &str) -> Result<String> {
-    let x = 0;
-    Ok(format!("{:?}", x))
+pub struct Day0;
+
+pub const SOLUTION: Solution = Solution::new::<Day0>();
+
+impl Day for Day0 {
+    const DAY: u32 = 0;
+    const TITLE: &'static str = "";
+    const EXAMPLE: &'static str = include_str!("../../../input/day0_ex");
+
+    type Input = Vec<i64>;
+    type Answer1 = i64;
+    type Answer2 = i64;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        parse(input)
+    }
+    fn part1(data: &Self::Input) -> Result<i64> {
+        part1(data)
+    }
+    fn part2(data: &Self::Input) -> Result<i64> {
+        part2(data)
+    }
+}
+
+pub fn part1(_data: &[i64]) -> Result<i64> {
+    Ok(0)
 }
 
-pub fn part2(input: &str) -> Result<String> {
-    let x = 0;
-    Ok(format!("{:?}", x))
+pub fn part2(_data: &[i64]) -> Result<i64> {
+    Ok(0)
 }
 
 fn parse(input: &str) -> Result<Vec<i64>> {
@@ -23,14 +46,14 @@ fn parse(input: &str) -> Result<Vec<i64>> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    const INPUT: &str = include_str!("../../../input/day1");
+    const INPUT: &str = include_str!("../../../input/day0");
 
     #[test]
     fn verify_p1() {
-        assert_eq!(part1(INPUT).unwrap().as_str(), "0")
+        assert_eq!(part1(&parse(INPUT).unwrap()).unwrap(), 0)
     }
     #[test]
     fn verify_p2() {
-        assert_eq!(part2(INPUT).unwrap().as_str(), "0")
+        assert_eq!(part2(&parse(INPUT).unwrap()).unwrap(), 0)
     }
 }
@@ -51,6 +51,7 @@ fn get_args() -> clap::ArgMatches<'static> {
         .version(clap::crate_version!())
         .about(clap::crate_description!())
         .setting(clap::AppSettings::DeriveDisplayOrder)
+        .setting(clap::AppSettings::SubcommandsNegateReqs)
         .arg(
             clap::Arg::with_name("verbosity")
                 .short("v")
@@ -58,8 +59,281 @@ fn get_args() -> clap::ArgMatches<'static> {
                 .global(true)
                 .help("Sets the level of verbosity"),
         )
-        .arg(clap::Arg::with_name("day").required(true))
-        .arg(clap::Arg::with_name("part").required(true))
-        .arg(clap::Arg::with_name("input").required(true))
+        .arg(
+            clap::Arg::with_name("list")
+                .long("list")
+                .help("List all available solutions"),
+        )
+        .arg(
+            clap::Arg::with_name("all")
+                .long("all")
+                .help("Run both parts of every solution"),
+        )
+        .arg(
+            clap::Arg::with_name("example")
+                .long("example")
+                .help("Use input/dayN_ex instead of input/dayN"),
+        )
+        .arg(
+            clap::Arg::with_name("format")
+                .long("format")
+                .takes_value(true)
+                .possible_values(&["text", "json"])
+                .default_value("text")
+                .help("Output format, json prints one object per line"),
+        )
+        .arg(
+            clap::Arg::with_name("record")
+                .long("record")
+                .help("Save answers that are not yet in answers.toml"),
+        )
+        .arg(
+            clap::Arg::with_name("stream")
+                .long("stream")
+                .conflicts_with("all")
+                .help("Solve line by line as the input is read, for days that support it"),
+        )
+        .arg(clap::Arg::with_name("day").required_unless_one(&["list", "all"]))
+        .arg(clap::Arg::with_name("part").required_unless_one(&["list", "all"]))
+        .arg(
+            clap::Arg::with_name("input")
+                .help("Path to the puzzle input, or `-` for stdin [default: input/dayN]"),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("bench")
+                .about("Time solutions over repeated runs")
+                .setting(clap::AppSettings::DeriveDisplayOrder)
+                .arg(
+                    clap::Arg::with_name("iterations")
+                        .short("n")
+                        .long("iterations")
+                        .takes_value(true)
+                        .help("Number of timed runs [default: 100]"),
+                )
+                .arg(
+                    clap::Arg::with_name("warmup")
+                        .long("warmup")
+                        .takes_value(true)
+                        .help("Number of untimed runs before timing [default: 5]"),
+                )
+                .arg(
+                    clap::Arg::with_name("example")
+                        .long("example")
+                        .help("Use input/dayN_ex instead of input/dayN"),
+                )
+                .arg(
+                    clap::Arg::with_name("save")
+                        .long("save")
+                        .takes_value(true)
+                        .help("Write results to a JSON baseline file"),
+                )
+                .arg(
+                    clap::Arg::with_name("baseline")
+                        .long("baseline")
+                        .takes_value(true)
+                        .help("Compare results against a saved JSON baseline"),
+                )
+                .arg(
+                    clap::Arg::with_name("threshold")
+                        .long("threshold")
+                        .takes_value(true)
+                        .help("Percent slowdown against the baseline to flag [default: 10]"),
+                )
+                .arg(clap::Arg::with_name("day").help("Day to benchmark [default: all]"))
+                .arg(clap::Arg::with_name("part").help("Part to benchmark [default: both]")),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("vents")
+                .about("Draw or query the day 5 vent lines, and how many cover each point")
+                .setting(clap::AppSettings::DeriveDisplayOrder)
+                .arg(
+                    clap::Arg::with_name("lines")
+                        .long("lines")
+                        .takes_value(true)
+                        .help(
+                            "Kinds of line to use, comma separated from horizontal, vertical, \
+                             diagonal, other, straight and all [default: all]",
+                        ),
+                )
+                .arg(
+                    clap::Arg::with_name("backend")
+                        .long("backend")
+                        .takes_value(true)
+                        .possible_values(&["auto", "dense", "sparse"])
+                        .default_value("auto")
+                        .help(
+                            "Count on a grid over the bounding box, or only the covered points. \
+                             The grid counts at most 65535 lines per point",
+                        ),
+                )
+                .arg(
+                    clap::Arg::with_name("threshold")
+                        .long("threshold")
+                        .takes_value(true)
+                        .help("Count the points covered by at least this many lines"),
+                )
+                .arg(
+                    clap::Arg::with_name("at")
+                        .long("at")
+                        .takes_value(true)
+                        .value_name("X,Y")
+                        .help("List the lines covering a point"),
+                )
+                .arg(
+                    clap::Arg::with_name("densest")
+                        .long("densest")
+                        .help("Find the point covered by the most lines"),
+                )
+                .arg(
+                    clap::Arg::with_name("format")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["ascii", "pgm", "ppm"])
+                        .default_value("ascii")
+                        .help("ascii is the puzzle's diagram, pgm and ppm are images"),
+                )
+                .arg(
+                    clap::Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .takes_value(true)
+                        .help("File to write to [default: stdout]"),
+                )
+                .arg(
+                    clap::Arg::with_name("example")
+                        .long("example")
+                        .help("Use input/day5_ex instead of input/day5"),
+                )
+                .arg(
+                    clap::Arg::with_name("input")
+                        .help("Path to the puzzle input, or `-` for stdin [default: input/day5]"),
+                ),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("fish")
+                .about("Count day 6 lanternfish after any number of days")
+                .setting(clap::AppSettings::DeriveDisplayOrder)
+                .arg(
+                    clap::Arg::with_name("days")
+                        .long("days")
+                        .takes_value(true)
+                        .help("Number of days to simulate [default: 256]"),
+                )
+                .arg(
+                    clap::Arg::with_name("breed")
+                        .long("breed")
+                        .takes_value(true)
+                        .help("Timer an adult resets to after spawning [default: 6]"),
+                )
+                .arg(
+                    clap::Arg::with_name("incubate")
+                        .long("incubate")
+                        .takes_value(true)
+                        .help("Timer a newborn fish starts with [default: 8]"),
+                )
+                .arg(
+                    clap::Arg::with_name("maturity")
+                        .long("maturity")
+                        .takes_value(true)
+                        .help("Days before the starting fish begin counting down [default: 0]"),
+                )
+                .arg(
+                    clap::Arg::with_name("modulus")
+                        .long("modulus")
+                        .takes_value(true)
+                        .help("Report the count modulo this number instead of exactly"),
+                )
+                .arg(
+                    clap::Arg::with_name("step")
+                        .long("step")
+                        .conflicts_with("modulus")
+                        .help("Simulate one day at a time, failing if the count overflows a u64"),
+                )
+                .arg(
+                    clap::Arg::with_name("big")
+                        .long("big")
+                        .help("Count with big integers in --step and --csv, so nothing overflows"),
+                )
+                .arg(
+                    clap::Arg::with_name("csv")
+                        .long("csv")
+                        .conflicts_with_all(&["step", "modulus"])
+                        .help("Print every day's timer counts and total as CSV"),
+                )
+                .arg(
+                    clap::Arg::with_name("exceeds")
+                        .long("exceeds")
+                        .takes_value(true)
+                        .value_name("N")
+                        .conflicts_with_all(&["step", "modulus", "csv"])
+                        .help("Print the first day the population is larger than N"),
+                )
+                .arg(
+                    clap::Arg::with_name("example")
+                        .long("example")
+                        .help("Use input/day6_ex instead of input/day6"),
+                )
+                .arg(
+                    clap::Arg::with_name("input")
+                        .help("Path to the puzzle input, or `-` for stdin [default: input/day6]"),
+                ),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("crabs")
+                .about("Align day 7 crabs under a custom fuel cost")
+                .setting(clap::AppSettings::DeriveDisplayOrder)
+                .arg(
+                    clap::Arg::with_name("cost")
+                        .long("cost")
+                        .takes_value(true)
+                        .possible_values(&["linear", "step", "quadratic"])
+                        .help("Built-in cost per crab [default: linear]"),
+                )
+                .arg(
+                    clap::Arg::with_name("poly")
+                        .long("poly")
+                        .takes_value(true)
+                        .value_name("C0,C1,..")
+                        .conflicts_with("cost")
+                        .help(
+                            "Cost as polynomial coefficients of the distance, lowest power first",
+                        ),
+                )
+                .arg(
+                    clap::Arg::with_name("targets")
+                        .short("k")
+                        .long("targets")
+                        .takes_value(true)
+                        .help("Number of positions the crabs may split between [default: 1]"),
+                )
+                .arg(
+                    clap::Arg::with_name("weights")
+                        .long("weights")
+                        .takes_value(true)
+                        .value_name("W0,W1,..")
+                        .help("Multiply each crab's cost by its weight"),
+                )
+                .arg(
+                    clap::Arg::with_name("cap")
+                        .long("cap")
+                        .takes_value(true)
+                        .help("Most fuel any one crab can spend"),
+                )
+                .arg(
+                    clap::Arg::with_name("example")
+                        .long("example")
+                        .help("Use input/day7_ex instead of input/day7"),
+                )
+                .arg(
+                    clap::Arg::with_name("input")
+                        .help("Path to the puzzle input, or `-` for stdin [default: input/day7]"),
+                ),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("new-day")
+                .about("Create a new day from the template module")
+                .arg(clap::Arg::with_name("day").required(true))
+                .arg(clap::Arg::with_name("title").help("Puzzle title")),
+        )
         .get_matches()
 }